@@ -41,6 +41,10 @@ fn main() -> anyhow::Result<()> {
         sewup::token::erc1155::SAFE_BATCH_TRANSFER_FROM_SIG => {
             sewup::token::erc1155::safe_batch_transfer_from(&contract)
         }
+        sewup::token::erc165::SUPPORTS_INTERFACE_SIG => sewup::token::erc165::supports_interface(
+            &contract,
+            &[sewup::token::erc1155::INTERFACE_ID],
+        ),
         _ => (),
     };
     Ok(())
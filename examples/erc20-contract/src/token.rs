@@ -2,7 +2,10 @@ use sewup_derive::{ewasm_constructor, ewasm_main, ewasm_test};
 
 #[ewasm_constructor]
 fn constructor() {
-    sewup::token::erc20::mint("0x8663DBF0cC68AaF37fC8BA262F2df4c666a41993", 1000);
+    sewup::token::erc20::set_cap(1_000_000);
+    // The minted values are added to the balance and the total supply
+    sewup::token::erc20::mint("0x8663DBF0cC68AaF37fC8BA262F2df4c666a41993", 600);
+    sewup::token::erc20::mint("0x8663DBF0cC68AaF37fC8BA262F2df4c666a41993", 400);
     // Following address format is also acceptable
     // sewup::token::erc20::mint("8663DBF0cC68AaF37fC8BA262F2df4c666a41993", 1000);
 }
@@ -16,10 +19,13 @@ fn main() -> anyhow::Result<()> {
         sewup::token::erc20::NAME_SIG => sewup::token::erc20::name("Demo"),
         sewup::token::erc20::SYMBOL_SIG => sewup::token::erc20::symbol("ETD"),
         sewup::token::erc20::DECIMALS_SIG => sewup::token::erc20::decimals(8),
-        sewup::token::erc20::TOTAL_SUPPLY_SIG => sewup::token::erc20::total_supply(1000),
+        sewup::token::erc20::TOTAL_SUPPLY_SIG => sewup::token::erc20::total_supply(&contract),
         sewup::token::erc20::APPROVE_SIG => sewup::token::erc20::approve(&contract),
         sewup::token::erc20::ALLOWANCE_SIG => sewup::token::erc20::allowance(&contract),
         sewup::token::erc20::TRANSFER_FROM_SIG => sewup::token::erc20::transfer_from(&contract),
+        sewup::token::erc20::BURN_SIG => sewup::token::erc20::burn(&contract),
+        sewup::token::erc20::BURN_FROM_SIG => sewup::token::erc20::burn_from(&contract),
+        sewup::token::erc20::CAP_SIG => sewup::token::erc20::cap(&contract),
         _ => (),
     };
     Ok(())
@@ -30,7 +36,8 @@ mod tests {
     use super::*;
     use hex_literal::hex;
     use sewup::erc20::{
-        BALANCE_OF_SIG, DECIMALS_SIG, NAME_SIG, SYMBOL_SIG, TOTAL_SUPPLY_SIG, TRANSFER_SIG,
+        ALLOWANCE_SIG, APPROVE_SIG, BALANCE_OF_SIG, BURN_FROM_SIG, BURN_SIG, CAP_SIG, DECIMALS_SIG,
+        NAME_SIG, SYMBOL_SIG, TOTAL_SUPPLY_SIG, TRANSFER_SIG,
     };
     use sewup_derive::{ewasm_assert_eq, ewasm_fn_sig};
 
@@ -104,4 +111,72 @@ mod tests {
             ]
         );
     }
+
+    #[ewasm_test]
+    fn test_execute_burn_operations() {
+        ewasm_assert_eq!(
+            cap(),
+            hex!("00000000000000000000000000000000000000000000000000000000000f4240").to_vec()
+        );
+
+        let burn_value = hex!("0000000000000000000000000000000000000000000000000000000000000064");
+        ewasm_assert_eq!(
+            burn(burn_value) by "0x8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+            vec![]
+        );
+
+        let owner = hex!("8663DBF0cC68AaF37fC8BA262F2df4c666a41993");
+        let mut input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.append(&mut owner.to_vec());
+        ewasm_assert_eq!(
+            balance_of(input_data),
+            hex!("0000000000000000000000000000000000000000000000000000000000000384").to_vec()
+        );
+        ewasm_assert_eq!(
+            total_supply(),
+            hex!("0000000000000000000000000000000000000000000000000000000000000384").to_vec()
+        );
+
+        let spender = hex!("0000000000000000000000000000000000000001");
+        let approve_value =
+            hex!("0000000000000000000000000000000000000000000000000000000000000032");
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.append(&mut spender.to_vec());
+        input_data.append(&mut approve_value.to_vec());
+        ewasm_assert_eq!(
+            approve(input_data) by "0x8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+            vec![]
+        );
+
+        // The spender burns the tokens of owner within the allowance
+        let burn_from_value =
+            hex!("000000000000000000000000000000000000000000000000000000000000001e");
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.append(&mut owner.to_vec());
+        input_data.append(&mut burn_from_value.to_vec());
+        ewasm_assert_eq!(
+            burn_from(input_data) by "0x0000000000000000000000000000000000000001",
+            vec![]
+        );
+
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.append(&mut owner.to_vec());
+        ewasm_assert_eq!(
+            balance_of(input_data),
+            hex!("0000000000000000000000000000000000000000000000000000000000000366").to_vec()
+        );
+        ewasm_assert_eq!(
+            total_supply(),
+            hex!("0000000000000000000000000000000000000000000000000000000000000366").to_vec()
+        );
+
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.append(&mut owner.to_vec());
+        input_data.append(&mut vec![0u8; 12]);
+        input_data.append(&mut spender.to_vec());
+        ewasm_assert_eq!(
+            allowance(input_data),
+            hex!("0000000000000000000000000000000000000000000000000000000000000014").to_vec()
+        );
+    }
 }
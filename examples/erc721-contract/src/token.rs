@@ -1,7 +1,19 @@
+#[cfg(target_arch = "wasm32")]
+use std::str::FromStr;
+
 use sewup_derive::{ewasm_constructor, ewasm_fn_sig, ewasm_main, ewasm_test};
 
+#[cfg(target_arch = "wasm32")]
+static MINTER: &str = "8663DBF0cC68AaF37fC8BA262F2df4c666a41993";
+
+/// The selector of `mint(uint256)`, which mints the token to the minter
+const MINT_SIG: [u8; 4] = ewasm_fn_sig!(mint(uint256));
+
 #[ewasm_constructor]
 fn constructor() {
+    sewup::token::erc721::set_name("Non-Fungible Token");
+    sewup::token::erc721::set_symbol("NFT");
+    sewup::token::erc721::set_minter("8663DBF0cC68AaF37fC8BA262F2df4c666a41993");
     sewup::token::erc721::mint(
         "8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
         vec![
@@ -12,14 +24,34 @@ fn constructor() {
     );
 }
 
+/// Mint the token in the input to the minter, which can only be called by the minter
+#[cfg(target_arch = "wasm32")]
+fn mint(contract: &sewup::primitives::Contract) -> anyhow::Result<()> {
+    sewup_derive::ewasm_call_only_by!(MINTER);
+    let token: String = contract.input_data[4..36]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    sewup::token::erc721::mint(MINTER, vec![&token]);
+    Ok(())
+}
+
 #[ewasm_main]
 fn main() -> anyhow::Result<()> {
     let contract = sewup::primitives::Contract::new()?;
     match contract.get_function_selector()? {
+        sewup::token::erc721::NAME_SIG => sewup::token::erc721::name(&contract),
+        sewup::token::erc721::SYMBOL_SIG => sewup::token::erc721::symbol(&contract),
         sewup::token::erc721::BALANCE_OF_SIG => sewup::token::erc721::balance_of(&contract),
         sewup::token::erc721::OWNER_OF_SIG => sewup::token::erc721::owner_of(&contract),
         sewup::token::erc721::TRANSFER_SIG => sewup::token::erc721::transfer(&contract),
         sewup::token::erc721::TRANSFER_FROM_SIG => sewup::token::erc721::transfer_from(&contract),
+        sewup::token::erc721::SAFE_TRANSFER_FROM_SIG => {
+            sewup::token::erc721::safe_transfer_from(&contract)
+        }
+        sewup::token::erc721::SAFE_TRANSFER_FROM_WITH_DATA_SIG => {
+            sewup::token::erc721::safe_transfer_from_with_data(&contract)
+        }
         sewup::token::erc721::APPROVE_SIG => sewup::token::erc721::approve(&contract),
         sewup::token::erc721::GET_APPROVED_SIG => sewup::token::erc721::get_approved(&contract),
         sewup::token::erc721::SET_APPROVAL_FOR_ALL_SIG => {
@@ -28,6 +60,22 @@ fn main() -> anyhow::Result<()> {
         sewup::token::erc721::IS_APPROVED_FOR_ALL_SIG => {
             sewup::token::erc721::is_approved_for_all(&contract)
         }
+        sewup::token::erc721::TOTAL_SUPPLY_SIG => sewup::token::erc721::total_supply(&contract),
+        sewup::token::erc721::TOKEN_BY_INDEX_SIG => sewup::token::erc721::token_by_index(&contract),
+        sewup::token::erc721::TOKEN_OF_OWNER_BY_INDEX_SIG => {
+            sewup::token::erc721::token_of_owner_by_index(&contract)
+        }
+        sewup::token::erc721::TOKEN_URI_SIG => sewup::token::erc721::token_uri(&contract),
+        sewup::token::erc721::SET_TOKEN_URI_SIG => sewup::token::erc721::set_token_uri(&contract),
+        MINT_SIG => mint(&contract)?,
+        sewup::token::erc165::SUPPORTS_INTERFACE_SIG => sewup::token::erc165::supports_interface(
+            &contract,
+            &[
+                sewup::token::erc721::INTERFACE_ID,
+                sewup::token::erc721::METADATA_INTERFACE_ID,
+                sewup::token::erc721::ENUMERABLE_INTERFACE_ID,
+            ],
+        ),
         _ => (),
     };
     Ok(())
@@ -37,7 +85,12 @@ fn main() -> anyhow::Result<()> {
 mod tests {
     use super::*;
     use hex_literal::hex;
-    use sewup::erc721::{BALANCE_OF_SIG, OWNER_OF_SIG, TRANSFER_SIG};
+    use sewup::erc721::{
+        BALANCE_OF_SIG, NAME_SIG, OWNER_OF_SIG, SAFE_TRANSFER_FROM_SIG,
+        SAFE_TRANSFER_FROM_WITH_DATA_SIG, SET_TOKEN_URI_SIG, SYMBOL_SIG, TOKEN_BY_INDEX_SIG,
+        TOKEN_OF_OWNER_BY_INDEX_SIG, TOKEN_URI_SIG, TOTAL_SUPPLY_SIG, TRANSFER_FROM_SIG,
+        TRANSFER_SIG,
+    };
     use sewup_derive::ewasm_assert_eq;
 
     #[ewasm_test]
@@ -88,4 +141,195 @@ mod tests {
             hex!("0000000000000000000000000000000000000000000000000000000000000001").to_vec()
         );
     }
+
+    #[ewasm_test]
+    fn test_execute_safe_transfer_from() {
+        let owner = hex!("8663DBF0cC68AaF37fC8BA262F2df4c666a41993");
+        let recipient = hex!("0000000000000000000000000000000000000001");
+        let token1 = hex!("0000000000000000000000000000000000000000000000000000000000000001");
+        let token2 = hex!("0000000000000000000000000000000000000000000000000000000000000002");
+
+        // The recipient has no code, so the transfer is done without calling `onERC721Received`
+        let mut input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.extend_from_slice(&owner);
+        input_data.extend_from_slice(&[0u8; 12]);
+        input_data.extend_from_slice(&recipient);
+        input_data.extend_from_slice(&token1);
+        ewasm_assert_eq!(
+            safe_transfer_from(input_data) by "8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+            vec![]
+        );
+        ewasm_assert_eq!(
+            owner_of(token1),
+            hex!("0000000000000000000000000000000000000000000000000000000000000001").to_vec()
+        );
+
+        // The data is placed after the head of arguments, which is 128 bytes
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.extend_from_slice(&owner);
+        input_data.extend_from_slice(&[0u8; 12]);
+        input_data.extend_from_slice(&recipient);
+        input_data.extend_from_slice(&token2);
+        input_data.extend_from_slice(&hex!(
+            "0000000000000000000000000000000000000000000000000000000000000080"
+            "0000000000000000000000000000000000000000000000000000000000000003"
+            "0102030000000000000000000000000000000000000000000000000000000000"
+        ));
+        ewasm_assert_eq!(
+            safe_transfer_from_with_data(input_data) by "8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+            vec![]
+        );
+        ewasm_assert_eq!(
+            owner_of(token2),
+            hex!("0000000000000000000000000000000000000000000000000000000000000001").to_vec()
+        );
+
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.extend_from_slice(&recipient);
+        ewasm_assert_eq!(
+            balance_of(input_data),
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 2
+            ]
+        );
+    }
+
+    #[ewasm_test]
+    fn test_execute_token_uri() {
+        ewasm_assert_eq!(
+            name(),
+            hex!(
+                "0000000000000000000000000000000000000000000000000000000000000020"
+                "0000000000000000000000000000000000000000000000000000000000000012"
+                "4e6f6e2d46756e6769626c6520546f6b656e0000000000000000000000000000"
+            )
+            .to_vec()
+        );
+        ewasm_assert_eq!(
+            symbol(),
+            hex!(
+                "0000000000000000000000000000000000000000000000000000000000000020"
+                "0000000000000000000000000000000000000000000000000000000000000003"
+                "4e46540000000000000000000000000000000000000000000000000000000000"
+            )
+            .to_vec()
+        );
+
+        let token1 = hex!("0000000000000000000000000000000000000000000000000000000000000001");
+        ewasm_assert_eq!(
+            token_uri(token1),
+            hex!(
+                "0000000000000000000000000000000000000000000000000000000000000020"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+            )
+            .to_vec()
+        );
+
+        // The uri is placed after the head of arguments, which is 64 bytes
+        let input_data = (
+            token1,
+            hex!("0000000000000000000000000000000000000000000000000000000000000040"),
+            hex!("0000000000000000000000000000000000000000000000000000000000000008"),
+            hex!("697066733a2f2f31000000000000000000000000000000000000000000000000"),
+        );
+        ewasm_assert_eq!(
+            set_token_uri(input_data) by "8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+            vec![]
+        );
+        ewasm_assert_eq!(
+            token_uri(token1),
+            hex!(
+                "0000000000000000000000000000000000000000000000000000000000000020"
+                "0000000000000000000000000000000000000000000000000000000000000008"
+                "697066733a2f2f31000000000000000000000000000000000000000000000000"
+            )
+            .to_vec()
+        );
+    }
+
+    #[ewasm_test]
+    fn test_execute_enumerable() {
+        let owner = hex!("8663DBF0cC68AaF37fC8BA262F2df4c666a41993");
+        let recipient = hex!("0000000000000000000000000000000000000001");
+        let token1 = hex!("0000000000000000000000000000000000000000000000000000000000000001");
+        let token2 = hex!("0000000000000000000000000000000000000000000000000000000000000002");
+        let token3 = hex!("0000000000000000000000000000000000000000000000000000000000000003");
+        let token4 = hex!("0000000000000000000000000000000000000000000000000000000000000004");
+        let index0 = hex!("0000000000000000000000000000000000000000000000000000000000000000");
+        let index1 = hex!("0000000000000000000000000000000000000000000000000000000000000001");
+        let index3 = hex!("0000000000000000000000000000000000000000000000000000000000000003");
+
+        ewasm_assert_eq!(
+            total_supply(),
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 3
+            ]
+        );
+        ewasm_assert_eq!(token_by_index(index0), token1.to_vec());
+        ewasm_assert_eq!(token_by_index(index1), token2.to_vec());
+
+        let mut input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.extend_from_slice(&owner);
+        input_data.extend_from_slice(&index1);
+        ewasm_assert_eq!(token_of_owner_by_index(input_data), token2.to_vec());
+
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.extend_from_slice(&owner);
+        input_data.extend_from_slice(&[0u8; 12]);
+        input_data.extend_from_slice(&recipient);
+        input_data.extend_from_slice(&token1);
+        ewasm_assert_eq!(
+            transfer_from(input_data) by "8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+            vec![]
+        );
+
+        // The last token of owner is moved to the index of the transferred token
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.extend_from_slice(&owner);
+        input_data.extend_from_slice(&index0);
+        ewasm_assert_eq!(token_of_owner_by_index(input_data), token3.to_vec());
+
+        input_data = vec![0u8, 0u8, 0u8, 0u8];
+        input_data.extend_from_slice(&recipient);
+        input_data.extend_from_slice(&index0);
+        ewasm_assert_eq!(token_of_owner_by_index(input_data), token1.to_vec());
+
+        // The transfer changes neither the supply nor the index of all tokens
+        ewasm_assert_eq!(
+            total_supply(),
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 3
+            ]
+        );
+        ewasm_assert_eq!(token_by_index(index0), token1.to_vec());
+
+        ewasm_assert_eq!(
+            mint(token4) by "8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+            vec![]
+        );
+        ewasm_assert_eq!(token_by_index(index3), token4.to_vec());
+
+        // Minting the token which is already owned is reverted, and nothing is changed
+        let remint = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ewasm_assert_eq!(
+                mint(token1) by "8663DBF0cC68AaF37fC8BA262F2df4c666a41993",
+                vec![]
+            );
+        }));
+        assert!(remint.is_err());
+        ewasm_assert_eq!(
+            total_supply(),
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 4
+            ]
+        );
+        ewasm_assert_eq!(
+            owner_of(token1),
+            hex!("0000000000000000000000000000000000000000000000000000000000000001").to_vec()
+        );
+    }
 }
@@ -110,6 +110,13 @@ fn check_ver_and_feat(
     Ok(().into())
 }
 
+#[ewasm_fn]
+fn migrate() -> anyhow::Result<sewup::primitives::EwasmAny> {
+    // Upgrade the storage saved by previous version of sewup
+    sewup::kv::Store::migrate()?;
+    Ok(().into())
+}
+
 #[ewasm_fn]
 fn check_buckets(buckets: Vec<String>) -> anyhow::Result<sewup::primitives::EwasmAny> {
     let mut storage = sewup::kv::Store::load(None)?;
@@ -238,8 +245,9 @@ fn main() -> anyhow::Result<sewup::primitives::EwasmAny> {
 
     let output = match contract.get_function_selector()? {
         ewasm_fn_sig!(check_ver_and_feat) => {
-            check_ver_and_feat(0, vec![sewup::kv::Feature::Default])?
+            check_ver_and_feat(3, vec![sewup::kv::Feature::Default])?
         }
+        ewasm_fn_sig!(migrate) => migrate()?,
         ewasm_fn_sig!(check_buckets) => {
             check_buckets(vec!["bucket1".to_string(), "bucket2".to_string()])?
         }
@@ -286,6 +294,8 @@ mod tests {
 
         ewasm_assert_ok!(check_ver_and_feat());
 
+        ewasm_assert_ok!(migrate());
+
         ewasm_assert_ok!(check_buckets());
 
         let input_pair_100 = Pair(
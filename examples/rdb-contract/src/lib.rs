@@ -58,10 +58,10 @@ fn check_tables() -> anyhow::Result<sewup::primitives::EwasmAny> {
             errors::RDBError::SimpleError("Person record_raw_size not correct".into()).into(),
         );
     }
-    if info.range.start != 2 {
+    if info.range.start != 0 {
         return Err(errors::RDBError::SimpleError("Person range start not correct".into()).into());
     }
-    if info.range.end != 2 {
+    if info.range.end != 0 {
         return Err(errors::RDBError::SimpleError("Person range end not correct".into()).into());
     }
     Ok(().into())
@@ -87,9 +87,10 @@ fn check_tables_again() -> anyhow::Result<sewup::primitives::EwasmAny> {
 #[ewasm_fn]
 fn get_children() -> anyhow::Result<sewup::primitives::EwasmAny> {
     let table = sewup::rdb::Db::load(None)?.table::<Person>()?;
-    let people = table.filter_records(&|p: &Person| p.age < 12)?;
+    let people = table.range_by_index("age", ..12u8)?;
 
     // you can do much complicate filter logic here as you like
+    // table.filter_records(&|p: &Person| p.age < 12 && !p.trusted)?
 
     let protocol: person::Protocol = people.into();
     Ok(sewup::primitives::EwasmAny::from(protocol))
@@ -109,6 +110,16 @@ fn get_post_author(input: Input) -> anyhow::Result<sewup::primitives::EwasmAny>
     Ok(sewup::primitives::EwasmAny::from(owner))
 }
 
+#[ewasm_fn]
+fn get_posts(input: Input) -> anyhow::Result<sewup::primitives::EwasmAny> {
+    // ( Person <- 1 --- many -> Post )
+    // use relationship to get the posts of the person
+    let posts = Person::posts(input.id)?;
+
+    let protocol: post::Protocol = posts.into();
+    Ok(sewup::primitives::EwasmAny::from(protocol))
+}
+
 #[ewasm_fn]
 fn get_home(input: Input) -> anyhow::Result<sewup::primitives::EwasmAny> {
     let table = sewup::rdb::Db::load(None)?.table::<Person>()?;
@@ -146,9 +157,10 @@ fn main() -> anyhow::Result<sewup::primitives::EwasmAny> {
         ewasm_fn_sig!(location::update) => ewasm_input_from!(contract move location::update),
         ewasm_fn_sig!(location::delete) => ewasm_input_from!(contract move location::delete),
         ewasm_fn_sig!(check_version_and_features) => {
-            check_version_and_features(0, vec![sewup::rdb::Feature::Default])
+            check_version_and_features(2, vec![sewup::rdb::Feature::Default])
         }
         ewasm_fn_sig!(get_post_author) => ewasm_input_from!(contract move get_post_author),
+        ewasm_fn_sig!(get_posts) => ewasm_input_from!(contract move get_posts),
         ewasm_fn_sig!(get_home) => ewasm_input_from!(contract move get_home),
         ewasm_fn_sig!(get_children) => get_children(),
         ewasm_fn_sig!(check_tables) => check_tables(),
@@ -164,6 +176,7 @@ mod tests {
     use super::*;
     use modules::{location, person, post, Location, Person, Post, LOCATION, PERSON, POST};
     use sewup::rdb::errors::Error as LibError;
+    use sewup::rdb::query::{Condition, Order};
     use sewup::types::{Raw, SizedString};
     use sewup_derive::{
         ewasm_assert_eq, ewasm_assert_ok, ewasm_auto_assert_eq, ewasm_err_output, ewasm_fn_sig,
@@ -188,7 +201,7 @@ mod tests {
             content: s.into(),
             person_id: 1,
         };
-        let mut create_post_input = post::protocol(post);
+        let mut create_post_input = post::protocol(post.clone());
         let mut expect_post_output = create_post_input.clone();
         expect_post_output.set_id(1);
         ewasm_auto_assert_eq!(post::create(create_post_input), expect_post_output);
@@ -199,6 +212,9 @@ mod tests {
 
         ewasm_auto_assert_eq!(get_post_author(Input { id: 1 }), person);
 
+        let posts: post::Protocol = vec![(1, post)].into();
+        ewasm_auto_assert_eq!(get_posts(Input { id: 1 }), posts);
+
         let child = Person {
             trusted: false,
             age: 9,
@@ -282,6 +298,55 @@ mod tests {
         }
     }
 
+    #[ewasm_test]
+    fn test_query_with_conditions() {
+        for i in 1..=10 {
+            let person = Person {
+                trusted: i % 2 == 0,
+                age: i as u8 * 10,
+                location_id: None,
+            };
+            let create_input = person::protocol(person);
+            let mut expect_output = create_input.clone();
+            expect_output.set_id(i);
+            ewasm_auto_assert_eq!(person::create(create_input), expect_output);
+        }
+
+        // The trusted people aged from 30 to 80, the oldest first, skip the first one and take
+        // two of them
+        let mut query = person::Protocol::default();
+        query.add_predicate("age", Condition::between(&30u8, &80u8));
+        query.add_predicate("trusted", Condition::equal(&true));
+        query.set_order_by("age", Order::Desc);
+        query.set_offset(1);
+        query.set_limit(2);
+        let expect_output: person::Protocol = vec![
+            (
+                6,
+                Person {
+                    trusted: true,
+                    age: 60,
+                    location_id: None,
+                },
+            ),
+            (
+                4,
+                Person {
+                    trusted: true,
+                    age: 40,
+                    location_id: None,
+                },
+            ),
+        ]
+        .into();
+        ewasm_auto_assert_eq!(person::get(query), expect_output);
+
+        let mut count_query = person::Protocol::default();
+        count_query.add_predicate("age", Condition::one_of(&[10u8, 20, 30, 45]).not());
+        count_query.set_count();
+        ewasm_auto_assert_eq!(person::get(count_query), 7usize);
+    }
+
     #[ewasm_test]
     fn test_table_management() {
         ewasm_assert_ok!(check_version_and_features());
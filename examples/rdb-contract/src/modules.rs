@@ -1,26 +1,33 @@
 use serde_derive::{Deserialize, Serialize};
 use sewup::types::Raw;
-use sewup_derive::{SizedString, Table};
+use sewup_derive::{foreign_keys, SizedString, Table};
 
 // Table derive provides the handers for CRUD,
 // to communicate with these handler, you will need protocol.
 // The protocol is easy to build by the `{struct_name}::protocol`, `{struct_name}::Protocol`,
 // please check out the test case in the end of this document
+//
+// The indexed fields are filtered by the index kept on chain, without comparing every record
+//
+// The foreign key fields, `location_id` and `person_id`, are declared by `#[foreign_keys]`
+#[foreign_keys]
 #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[belongs_none_or(Location)]
+#[has_many(Post)]
 pub struct Person {
+    #[index]
     pub trusted: bool,
+    #[index]
     pub age: u8,
-    pub location_id: Option<usize>,
 }
 
+// The posts are deleted with the person, the person can not be deleted if there are posts
+// without `on_delete = cascade`
+#[foreign_keys]
 #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
-#[belongs_to(Person)]
+#[belongs_to(Person, on_delete = cascade)]
 pub struct Post {
     pub content: SizedString!(50),
-
-    // Currently, this field need to set up manually, this will be enhance later
-    pub person_id: usize,
 }
 
 #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -1150,6 +1150,37 @@ pub fn derive_value(item: TokenStream) -> TokenStream {
 /// ewasm_output_from!(handler_input)
 /// ```
 ///
+/// The query of `{struct_name}::get` can have conditions on fields, the order, the page, and
+/// also can count the records, the conditions of [`sewup::rdb::query::Condition`] are
+/// `equal`, `gt`, `ge`, `lt`, `le`, `between`, `one_of`, and `not`.
+/// The records are sorted by id if the order is not specified, and the handler outputs the
+/// number of matched records instead of the protocol if `set_count` is used.
+/// The fields are compared with the ordered encoding of values, where `-0.0` equals `0.0`, and
+/// the maps with the same entries are equal, such as `HashMap` in any iteration order.
+///
+/// ```compile_fail
+/// let mut query = person::Protocol::default();
+/// query.add_predicate("age", Condition::between(&18u8, &65u8));
+/// query.add_predicate("location_id", Condition::equal(&None::<usize>).not());
+/// query.set_order_by("age", Order::Desc);
+/// query.set_offset(10);
+/// query.set_limit(10);
+/// ```
+///
+/// The matched records can be aggregated with [`sewup::rdb::query::Aggregate`] by
+/// `set_aggregate`, then the handler outputs `Option<i128>`, or grouped by a field with
+/// `set_group_by`, then the handler outputs `Vec<(Vec<u8>, Option<i128>)>` in the order of the
+/// field, and the value of field in each group is the bincode binary of the field type.
+///
+/// ```compile_fail
+/// let mut query = person::Protocol::default();
+/// query.add_predicate("trusted", Condition::equal(&true));
+/// query.set_aggregate(Aggregate::Max("age".into()));
+///
+/// let mut query = person::Protocol::default();
+/// query.set_group_by("trusted", Aggregate::Count);
+/// ```
+///
 /// Please note that the protocol default and the protocol for default instance may be different.
 /// This base on the implementation of the default trait of the structure.
 ///
@@ -1159,29 +1190,41 @@ pub fn derive_value(item: TokenStream) -> TokenStream {
 /// assert!(default_input != default_person_input)
 /// ```
 ///
-/// The relation can be set with `#[belongs_to(Person)]` or `#[belongs_none_or(Person)]`
+/// The relation can be set with `#[belongs_to(Person)]` or `#[belongs_none_or(Person)]`, and the
+/// foreign key field `person_id` is declared by `#[foreign_keys]`, or it can be declared by hand.
 /// ```compile_fail
+/// #[foreign_keys]
 /// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
 /// #[belongs_none_or(Location)]
+/// #[has_many(Post)]
 /// pub struct Person {
 ///     pub trusted: bool,
 ///     pub age: u8,
-///     pub location_id: Option<usize>,
 /// }
 ///
+/// #[foreign_keys]
 /// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
 /// #[belongs_to(Person)]
+/// #[many_to_many(Tag, through = PostTag)]
 /// pub struct Post {
 ///     pub content: SizedString!(50),
-///
-///     // Currently, this field need to set up manually, this will be enhance later
-///     pub person_id: usize,
 /// }
 ///
 /// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
 /// pub struct Location {
 ///     pub address: Raw,
 /// }
+///
+/// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// pub struct Tag {
+///     pub name: SizedString!(10),
+/// }
+///
+/// #[foreign_keys]
+/// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// #[belongs_to(Post)]
+/// #[belongs_to(Tag)]
+/// pub struct PostTag {}
 /// ```
 /// After the directive setup, you can use `table_name.another_table_name` to get the related
 /// instance, for example
@@ -1189,39 +1232,148 @@ pub fn derive_value(item: TokenStream) -> TokenStream {
 /// let person: Person = post.person()?;
 /// let home: Option<Location> = person.location()?;
 /// ```
+/// The parent record of foreign key is checked when the record is committed, and the
+/// `on_delete` option decides what happens to the child records when the parent record is
+/// deleted, `restrict` (default) refuses the commit of parent table, `cascade` deletes the child
+/// records, and `set_none` sets the foreign key to `None` for `#[belongs_none_or]`.
+/// The child records are deleted or detached when the parent table is committed, the rows of
+/// deleted children and the grandchildren deleted by them are wiped, and the detached children
+/// are re-keyed in the index of foreign key, whose foreign key is set to `None` when the child
+/// table is loaded. The foreign key fields are always indexed, such that the children are found
+/// without loading the child tables.
+/// ```compile_fail
+/// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// #[belongs_to(Person, on_delete = cascade)]
+/// #[belongs_none_or(Location, on_delete = set_none)]
+/// pub struct Post {
+///     pub content: SizedString!(50),
+///     pub person_id: usize,
+///     pub location_id: Option<usize>,
+/// }
+/// ```
+/// The record does not keep its id, so the children of `#[has_many]` and the records related
+/// through the join table of `#[many_to_many]` are got with the id of record, for example
+/// ```compile_fail
+/// let posts: Vec<(usize, Post)> = Person::posts(person_id)?;
+/// Post::add_tag(post_id, tag_id)?;
+/// let tags: Vec<(usize, Tag)> = Post::tags(post_id)?;
+/// Post::remove_tag(post_id, tag_id)?;
+/// ```
+/// The accessor is named as the lowercase table name with `s`, which can be named with the
+/// `accessor` option, for example `#[has_many(Category, accessor = categories)]` gives
+/// `Person::categories(person_id)`, and `#[many_to_many(Category, through = PostCategory,
+/// accessor = categories)]` gives `Post::categories(post_id)`.
+/// The foreign keys of the join table can be declared with `#[belongs_to]` or
+/// `#[belongs_none_or]`, and the join records with `None` are not related to any record.
+///
+/// The fields can be indexed with `#[index]`, or `#[unique]` for the index with unique values,
+/// the indexes are kept on chain and updated when the records are added or updated, such that
+/// the equality filter of `{struct_name}::get` and `Table::find_by_index`,
+/// `Table::range_by_index` do not compare every record.
+/// Adding or updating a record with an existing value of unique field returns
+/// `rdb::errors::Error::UniqueViolation`.
+/// ```compile_fail
+/// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// pub struct Person {
+///     #[unique]
+///     pub name: SizedString!(20),
+///     #[index]
+///     pub age: u8,
+/// }
+///
+/// let adults = table.range_by_index("age", 18u8..)?;
+/// ```
+///
+/// The table is named by the type name of struct, so the records are not found if the struct is
+/// moved to another module, `#[table(name = "...")]` gives the table a name which does not
+/// change with the path of struct.
+/// The table saved with the type name, or the table of a renamed struct, is associated to the
+/// new name with `Db::rename_table`.
+/// The records are saved with the schema version of `#[table(version = ...)]`, which is 0 by
+/// default, and the table saved with another version can not be loaded, please keep the old
+/// struct with the same table name and rewrite the records with `Db::migrate_table`.
+/// ```compile_fail
+/// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// #[table(name = "person", version = 1)]
+/// pub struct PersonV1 {
+///     pub trusted: bool,
+///     pub age: u8,
+/// }
+///
+/// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// #[table(name = "person", version = 2)]
+/// pub struct Person {
+///     pub trusted: bool,
+///     pub age: u16,
+///     pub name: SizedString!(20),
+/// }
+///
+/// Db::load(None)?
+///     .migrate_table::<PersonV1, Person>(|p| Person {
+///         trusted: p.trusted,
+///         age: p.age as u16,
+///         ..Default::default()
+///     })?
+///     .commit()?;
+/// ```
 #[cfg(feature = "rdb")]
-#[proc_macro_derive(Table, attributes(belongs_to, belongs_none_or))]
+#[proc_macro_derive(
+    Table,
+    attributes(
+        belongs_to,
+        belongs_none_or,
+        has_many,
+        many_to_many,
+        index,
+        unique,
+        table
+    )
+)]
 pub fn derive_table(item: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(item as syn::DeriveInput);
     let attrs = &input.attrs;
-    let mut belongs_to: Option<String> = None;
-    let mut belongs_none_or: Option<String> = None;
+    let mut belongs_to: Vec<(String, proc_macro2::TokenStream)> = Vec::new();
+    let mut belongs_none_or: Vec<(String, proc_macro2::TokenStream)> = Vec::new();
+    let mut has_many: Vec<(String, Option<String>)> = Vec::new();
+    let mut many_to_many: Vec<(String, String, Option<String>)> = Vec::new();
     for a in attrs.iter() {
-        let syn::Attribute { path, tokens, .. } = a;
-        let attr_name = path.segments.first().map(|s| s.ident.to_string());
-        if Some("belongs_to".to_string()) == attr_name {
-            belongs_to = Some(
-                tokens
-                    .to_string()
-                    .strip_prefix('(')
-                    .expect("#[belongs_to(table_name)] is not correct")
-                    .strip_suffix(')')
-                    .expect("#[belongs_to(table_name)] is not correct")
-                    .to_string(),
-            );
-        }
-        if Some("belongs_none_or".to_string()) == attr_name {
-            belongs_none_or = Some(
-                tokens
-                    .to_string()
-                    .strip_prefix('(')
-                    .expect("#[belongs_none_or(table_name)] is not correct")
-                    .strip_suffix(')')
-                    .expect("#[belongs_none_or(table_name)] is not correct")
-                    .to_string(),
-            );
+        match relation_of(a) {
+            Some((name, table, options)) if name == "belongs_to" => {
+                belongs_to.push((table, on_delete_of(a, &options, false)))
+            }
+            Some((name, table, options)) if name == "belongs_none_or" => {
+                belongs_none_or.push((table, on_delete_of(a, &options, true)))
+            }
+            Some((name, table, mut options)) if name == "has_many" => {
+                has_many.push((table, options.remove("accessor")))
+            }
+            Some((name, table, mut options)) if name == "many_to_many" => {
+                match options.remove("through") {
+                    Some(through) => {
+                        many_to_many.push((table, through, options.remove("accessor")))
+                    }
+                    None => abort!(
+                        a,
+                        "#[many_to_many(table_name, through = join_table_name)] is not correct"
+                    ),
+                }
+            }
+            _ => (),
         }
     }
+    let (table_name, schema_version) = table_options_of(attrs);
+    let table_name = table_name.map(|name| {
+        quote! {
+            fn table_name() -> &'static str {
+                #name
+            }
+        }
+    });
+    let schema_version = schema_version.map(|version| {
+        quote! {
+            const SCHEMA_VERSION: u32 = #version;
+        }
+    });
     let struct_name = &input.ident;
     let fields_with_type = match &input.data {
         syn::Data::Struct(syn::DataStruct {
@@ -1237,6 +1389,76 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         _ => abort!(&input.ident, "Table derive only use for struct"),
     };
 
+    let index_fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(f),
+            ..
+        }) => f
+            .named
+            .iter()
+            .filter_map(|f| {
+                let attr_names = f
+                    .attrs
+                    .iter()
+                    .filter_map(|a| a.path.segments.first().map(|s| s.ident.to_string()))
+                    .collect::<Vec<_>>();
+                if attr_names.contains(&"unique".to_string()) {
+                    Some((f.ident.clone().unwrap(), true))
+                } else if attr_names.contains(&"index".to_string()) {
+                    Some((f.ident.clone().unwrap(), false))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    // The foreign keys are indexed, such that the child records of a parent record are found
+    // without comparing every record
+    let mut index_fields = index_fields;
+    for (parent_table, _) in belongs_to.iter().chain(belongs_none_or.iter()) {
+        let field = Ident::new(
+            &format!("{}_id", parent_table.to_ascii_lowercase()),
+            Span::call_site(),
+        );
+        if !index_fields.iter().any(|(f, _)| *f == field) {
+            index_fields.push((field, false));
+        }
+    }
+    let foreign_keys = belongs_to
+        .iter()
+        .map(|(parent_table, on_delete)| (parent_table, on_delete, false))
+        .chain(
+            belongs_none_or
+                .iter()
+                .map(|(parent_table, on_delete)| (parent_table, on_delete, true)),
+        )
+        .map(|(parent_table, on_delete, optional)| {
+            let field = Ident::new(
+                &format!("{}_id", parent_table.to_ascii_lowercase()),
+                Span::call_site(),
+            );
+            let field_str = field.to_string();
+            let parent_table = Ident::new(parent_table, Span::call_site());
+            let (id, set_none) = if optional {
+                (quote!(|r| r.#field), quote!(Some(|r| r.#field = None)))
+            } else {
+                (quote!(|r| Some(r.#field)), quote!(None))
+            };
+            quote! {
+                sewup::rdb::traits::ForeignKey {
+                    field: #field_str,
+                    parent: <#parent_table as sewup::rdb::traits::Record>::table_name,
+                    on_delete: #on_delete,
+                    id: #id,
+                    set_none: #set_none,
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    let index_field_strs = index_fields.iter().map(|(f, _)| f.to_string());
+    let index_field_uniques = index_fields.iter().map(|(_, u)| u);
+
     let mut wrapper_fields = vec![(
         Ident::new("id", Span::call_site()),
         syn::Type::Path(syn::TypePath {
@@ -1263,7 +1485,9 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
     let clone_field_names3 = field_names.clone();
     let clone_field_names4 = field_names.clone();
     let clone_field_names5 = field_names.clone();
-    let field_types = fields_with_type.iter().map(|(_, t)| t);
+    let clone_field_names6 = field_names.clone();
+    let field_strs = fields_with_type.iter().map(|(f, _)| f.to_string());
+    let field_strs2 = field_strs.clone();
 
     let protocol_name = Ident::new(&format!("{}Protocol", struct_name), Span::call_site());
     let wrapper_name = Ident::new(&format!("{}Wrapper", struct_name), Span::call_site());
@@ -1276,14 +1500,40 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         Span::call_site(),
     );
     let mut output = quote!(
-        impl sewup::rdb::traits::Record for #struct_name {}
+        impl sewup::rdb::traits::Record for #struct_name {
+            const INDEXES: &'static [(&'static str, bool)] = &[
+                #((#index_field_strs, #index_field_uniques),)*
+            ];
+
+            #schema_version
+
+            #table_name
+
+            fn field_key(&self, field: &str) -> sewup::Result<Vec<u8>> {
+                match field {
+                    #(#field_strs => Ok(sewup::ordered::to_ordered_bytes(&self.#clone_field_names6)?),)*
+                    _ => Err(sewup::rdb::errors::Error::UnknownField(field.into()).into()),
+                }
+            }
+
+            fn foreign_keys() -> Vec<sewup::rdb::traits::ForeignKey<Self>> {
+                vec![#(#foreign_keys,)*]
+            }
+        }
 
         #[cfg_attr(any(feature = "debug", test), derive(Debug))]
         #[derive(Clone, sewup::Serialize, sewup::Deserialize)]
         pub struct #protocol_name {
             pub select_fields: Option<std::collections::HashSet::<String>>,
             pub filter: bool,
-            pub records: Vec<#wrapper_name>
+            pub records: Vec<#wrapper_name>,
+            pub predicates: Vec<sewup::rdb::query::Predicate>,
+            pub order_by: Option<(String, sewup::rdb::query::Order)>,
+            pub limit: Option<usize>,
+            pub offset: usize,
+            pub count: bool,
+            pub aggregate: Option<sewup::rdb::query::Aggregate>,
+            pub group_by: Option<String>,
         }
 
         impl #protocol_name {
@@ -1305,7 +1555,14 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
                 Self {
                     select_fields: None,
                     filter: false,
-                    records: vec![Default::default()]
+                    records: vec![Default::default()],
+                    predicates: Vec::new(),
+                    order_by: None,
+                    limit: None,
+                    offset: 0,
+                    count: false,
+                    aggregate: None,
+                    group_by: None,
                 }
             }
         }
@@ -1313,9 +1570,8 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         impl From<#struct_name> for #protocol_name {
             fn from(instance: #struct_name) -> Self {
                 Self {
-                    select_fields: None,
-                    filter: false,
-                    records: vec![instance.into()]
+                    records: vec![instance.into()],
+                    ..Default::default()
                 }
             }
         }
@@ -1323,9 +1579,8 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         impl From<(usize, #struct_name)> for #protocol_name {
             fn from(instance: (usize, #struct_name)) -> Self {
                 Self {
-                    select_fields: None,
-                    filter: false,
-                    records: vec![instance.into()]
+                    records: vec![instance.into()],
+                    ..Default::default()
                 }
             }
         }
@@ -1333,9 +1588,8 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         impl From<Vec<#struct_name>> for #protocol_name {
             fn from(instances: Vec<#struct_name>) -> Self {
                 Self {
-                    select_fields: None,
-                    filter: false,
-                    records: instances.into_iter().map(|i| i.into()).collect::<Vec<_>>()
+                    records: instances.into_iter().map(|i| i.into()).collect::<Vec<_>>(),
+                    ..Default::default()
                 }
             }
         }
@@ -1343,9 +1597,8 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         impl From<Vec<(usize, #struct_name)>> for #protocol_name {
             fn from(instances: Vec<(usize, #struct_name)>) -> Self {
                 Self {
-                    select_fields: None,
-                    filter: false,
-                    records: instances.into_iter().map(|i| i.into()).collect::<Vec<_>>()
+                    records: instances.into_iter().map(|i| i.into()).collect::<Vec<_>>(),
+                    ..Default::default()
                 }
             }
         }
@@ -1353,9 +1606,8 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         impl From<Vec<#wrapper_name>> for #protocol_name {
             fn from(records: Vec<#wrapper_name>) -> Self {
                 Self {
-                    select_fields: None,
-                    filter: false,
                     records,
+                    ..Default::default()
                 }
             }
         }
@@ -1408,29 +1660,35 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
             pub fn get(proc: Protocol) -> sewup::Result<sewup::primitives::EwasmAny> {
                 let table = sewup::rdb::Db::load(None)?.table::<_InstanceType>()?;
                 if proc.filter {
-                    let mut raw_output: Vec<Wrapper> = Vec::new();
-                    for (idx, r) in table.all_records()?.drain(..).enumerate(){
-                        let mut all_field_match = true;
+                    let mut predicates = proc.predicates.clone();
+                    if let Some(query) = proc.records.first() {
                         #(
-                            paste::paste! {
-                                let [<#clone_field_names2 _filed_filter>] : Option<#field_types> =
-                                    sewup::utils::get_field_by_name(proc.records[0].clone(), stringify!(#clone_field_names2));
-
-                                let  [<#clone_field_names2 _field>] : #field_types =
-                                    sewup::utils::get_field_by_name(&r, stringify!(#clone_field_names2));
-
-                                if [<#clone_field_names2 _filed_filter>].is_some() {
-                                    all_field_match &=
-                                        [<#clone_field_names2 _filed_filter>].unwrap()
-                                            == [<#clone_field_names2 _field>];
-                                }
+                            if let Some(value) = query.#clone_field_names2.as_ref() {
+                                predicates.push(sewup::rdb::query::Predicate::new(
+                                    #field_strs2,
+                                    sewup::rdb::query::Condition::equal(value),
+                                ));
                             }
-                         )*
-
-                        if all_field_match {
-                            raw_output.push((idx + 1, r).into());
+                        )*
+                    }
+                    if proc.count {
+                        return Ok(table.count(&predicates)?.into());
+                    }
+                    if let Some(aggregate) = proc.aggregate.as_ref() {
+                        if let Some(field) = proc.group_by.as_ref() {
+                            let mut groups: Vec<(Vec<u8>, Option<i128>)> = Vec::new();
+                            for (value, output) in table.group_by(field, aggregate, &predicates)? {
+                                groups.push((sewup::bincode::serialize(&value)?, output));
+                            }
+                            return Ok(groups.into());
                         }
+                        return Ok(table.aggregate(aggregate, &predicates)?.into());
                     }
+                    let mut raw_output: Vec<Wrapper> = table
+                        .query(&predicates, proc.order_by.as_ref(), proc.offset, proc.limit)?
+                        .into_iter()
+                        .map(|r| r.into())
+                        .collect();
                     if let Some(select_fields) = proc.select_fields {
                         for w in raw_output.iter_mut() {
                             #(
@@ -1492,6 +1750,35 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
                     #(self.records[0].#clone_field_names4.is_none() && )*
                     true
                 }
+                pub fn add_predicate(&mut self, field: &str, condition: sewup::rdb::query::Condition) {
+                    self.filter = true;
+                    self.predicates.push(sewup::rdb::query::Predicate::new(field, condition));
+                }
+                pub fn set_order_by(&mut self, field: &str, order: sewup::rdb::query::Order) {
+                    self.filter = true;
+                    self.order_by = Some((field.into(), order));
+                }
+                pub fn set_limit(&mut self, limit: usize) {
+                    self.filter = true;
+                    self.limit = Some(limit);
+                }
+                pub fn set_offset(&mut self, offset: usize) {
+                    self.filter = true;
+                    self.offset = offset;
+                }
+                pub fn set_count(&mut self) {
+                    self.filter = true;
+                    self.count = true;
+                }
+                pub fn set_aggregate(&mut self, aggregate: sewup::rdb::query::Aggregate) {
+                    self.filter = true;
+                    self.aggregate = Some(aggregate);
+                }
+                pub fn set_group_by(&mut self, field: &str, aggregate: sewup::rdb::query::Aggregate) {
+                    self.filter = true;
+                    self.aggregate = Some(aggregate);
+                    self.group_by = Some(field.into());
+                }
             }
             pub fn query(instance: _InstanceType) -> Wrapper {
                 instance.into()
@@ -1500,19 +1787,19 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
             impl From<Query> for Protocol {
                 fn from(instance: Query) -> Self {
                     Self {
-                        select_fields: None,
                         filter: true,
-                        records: vec![instance.into()]
+                        records: vec![instance.into()],
+                        ..Default::default()
                     }
                 }
             }
         }
     ).to_string();
 
-    if let Some(parent_table) = belongs_to {
+    for (parent_table, _) in belongs_to {
         let lower_parent_table = &format!("{}", &parent_table).to_ascii_lowercase();
         let parent_table = Ident::new(&parent_table, Span::call_site());
-        let lower_parent_table_ident = Ident::new(&lower_parent_table, Span::call_site());
+        let lower_parent_table_ident = Ident::new(lower_parent_table, Span::call_site());
         let field_name = &format!("{}_id", lower_parent_table);
 
         output += &quote! {
@@ -1527,10 +1814,10 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         .to_string();
     }
 
-    if let Some(parent_table) = belongs_none_or {
+    for (parent_table, _) in belongs_none_or {
         let lower_parent_table = &format!("{}", &parent_table).to_ascii_lowercase();
         let parent_table = Ident::new(&parent_table, Span::call_site());
-        let lower_parent_table_ident = Ident::new(&lower_parent_table, Span::call_site());
+        let lower_parent_table_ident = Ident::new(lower_parent_table, Span::call_site());
         let field_name = &format!("{}_id", lower_parent_table);
 
         output += &quote! {
@@ -1552,9 +1839,279 @@ pub fn derive_table(item: TokenStream) -> TokenStream {
         .to_string();
     }
 
+    let foreign_key = format!("{}_id", lower_name);
+    for (child_table, accessor) in has_many {
+        let children = Ident::new(
+            &accessor.unwrap_or_else(|| format!("{}s", child_table.to_ascii_lowercase())),
+            Span::call_site(),
+        );
+        let child_table = Ident::new(&child_table, Span::call_site());
+
+        output += &quote! {
+            impl #struct_name {
+                /// The records with the foreign key of the record with the id, the foreign key of
+                /// children may be declared with `#[belongs_to]` or `#[belongs_none_or]`
+                pub fn #children (id: usize) -> sewup::Result<Vec<(usize, #child_table)>> {
+                    let child_table = sewup::rdb::Db::load(None)?.table::<#child_table>()?;
+                    let condition = sewup::rdb::query::Condition::In(vec![
+                        sewup::ordered::to_ordered_bytes(&id)?,
+                        sewup::ordered::to_ordered_bytes(&Some(id))?,
+                    ]);
+                    child_table.query(
+                        &[sewup::rdb::query::Predicate::new(#foreign_key, condition)],
+                        None,
+                        0,
+                        None,
+                    )
+                }
+            }
+        }
+        .to_string();
+    }
+
+    for (other_table, join_table, accessor) in many_to_many {
+        let lower_other_table = other_table.to_ascii_lowercase();
+        let others = Ident::new(
+            &accessor.unwrap_or_else(|| format!("{}s", lower_other_table)),
+            Span::call_site(),
+        );
+        let add_other = Ident::new(&format!("add_{}", lower_other_table), Span::call_site());
+        let remove_other = Ident::new(&format!("remove_{}", lower_other_table), Span::call_site());
+        let other_key = Ident::new(&format!("{}_id", lower_other_table), Span::call_site());
+        let other_key_str = other_key.to_string();
+        let self_key = Ident::new(&foreign_key, Span::call_site());
+        let other_table = Ident::new(&other_table, Span::call_site());
+        let join_table = Ident::new(&join_table, Span::call_site());
+
+        output += &quote! {
+            impl #struct_name {
+                /// The records related to the record with the id through the join table
+                pub fn #others (id: usize) -> sewup::Result<Vec<(usize, #other_table)>> {
+                    let join_table = sewup::rdb::Db::load(None)?.table::<#join_table>()?;
+                    let other_table = sewup::rdb::Db::load(None)?.table::<#other_table>()?;
+                    let mut others = Vec::new();
+                    for (_, r) in join_table
+                        .query(
+                            &[sewup::rdb::query::Predicate::new(
+                                #foreign_key,
+                                sewup::rdb::query::Condition::In(vec![
+                                    sewup::ordered::to_ordered_bytes(&id)?,
+                                    sewup::ordered::to_ordered_bytes(&Some(id))?,
+                                ]),
+                            )],
+                            None,
+                            0,
+                            None,
+                        )?
+                        .into_iter()
+                    {
+                        let other_id: Option<usize> = r.#other_key.into();
+                        if let Some(other_id) = other_id {
+                            others.push((other_id, other_table.get_record(other_id)?));
+                        }
+                    }
+                    Ok(others)
+                }
+
+                /// Relate the record with the id to another record, and returns the id of the
+                /// record in the join table
+                #[allow(clippy::field_reassign_with_default, clippy::useless_conversion)]
+                pub fn #add_other (id: usize, other_id: usize) -> sewup::Result<usize> {
+                    let mut join_table = sewup::rdb::Db::load(None)?.table::<#join_table>()?;
+                    let mut record = #join_table::default();
+                    record.#self_key = id.into();
+                    record.#other_key = other_id.into();
+                    let join_id = join_table.add_record(record)?;
+                    join_table.commit()?;
+                    Ok(join_id)
+                }
+
+                /// Remove the records in the join table relating the records
+                pub fn #remove_other (id: usize, other_id: usize) -> sewup::Result<()> {
+                    let mut join_table = sewup::rdb::Db::load(None)?.table::<#join_table>()?;
+                    let joins = join_table.query(
+                        &[
+                            sewup::rdb::query::Predicate::new(
+                                #foreign_key,
+                                sewup::rdb::query::Condition::In(vec![
+                                    sewup::ordered::to_ordered_bytes(&id)?,
+                                    sewup::ordered::to_ordered_bytes(&Some(id))?,
+                                ]),
+                            ),
+                            sewup::rdb::query::Predicate::new(
+                                #other_key_str,
+                                sewup::rdb::query::Condition::In(vec![
+                                    sewup::ordered::to_ordered_bytes(&other_id)?,
+                                    sewup::ordered::to_ordered_bytes(&Some(other_id))?,
+                                ]),
+                            ),
+                        ],
+                        None,
+                        0,
+                        None,
+                    )?;
+                    for (join_id, _) in joins.into_iter() {
+                        join_table.update_record(join_id, None)?;
+                    }
+                    join_table.commit()?;
+                    Ok(())
+                }
+            }
+        }
+        .to_string();
+    }
+
     output.parse().unwrap()
 }
 
+/// Returns the name of attribute, the table and the options of the relation directive, for
+/// example `#[belongs_to(Person, on_delete = cascade)]` or
+/// `#[many_to_many(Tag, through = PostTag)]`
+#[cfg(feature = "rdb")]
+fn relation_of(
+    attr: &syn::Attribute,
+) -> Option<(String, String, std::collections::HashMap<String, String>)> {
+    let name = attr.path.segments.first()?.ident.to_string();
+    if !["belongs_to", "belongs_none_or", "has_many", "many_to_many"].contains(&name.as_str()) {
+        return None;
+    }
+    let tokens = attr.tokens.to_string();
+    let args = tokens
+        .strip_prefix('(')
+        .and_then(|t| t.strip_suffix(')'))
+        .unwrap_or_else(|| abort!(attr, "#[{}(table_name)] is not correct", name));
+    let mut args = args.split(',').map(|a| a.trim());
+    let table = args.next().unwrap_or_default().to_string();
+    let mut options = std::collections::HashMap::new();
+    for option in args {
+        match option.split_once('=') {
+            Some((key, value)) => options.insert(key.trim().to_string(), value.trim().to_string()),
+            None => abort!(
+                attr,
+                "the option `{}` of #[{}] is not correct",
+                option,
+                name
+            ),
+        };
+    }
+    Some((name, table, options))
+}
+
+/// The `name` and `version` options of `#[table(name = "...", version = ...)]`
+#[cfg(feature = "rdb")]
+fn table_options_of(attrs: &[syn::Attribute]) -> (Option<String>, Option<u32>) {
+    let (mut name, mut version) = (None, None);
+    for attr in attrs.iter().filter(|a| a.path.is_ident("table")) {
+        let nested = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list.nested,
+            _ => abort!(
+                attr,
+                "#[table(name = \"...\", version = ...)] is not correct"
+            ),
+        };
+        for meta in nested.iter() {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(s),
+                    ..
+                })) if path.is_ident("name") => name = Some(s.value()),
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Int(i),
+                    ..
+                })) if path.is_ident("version") => match i.base10_parse::<u32>() {
+                    Ok(v) => version = Some(v),
+                    Err(_) => abort!(i, "the version of table should be an u32"),
+                },
+                _ => abort!(meta, "the option of #[table] is not correct"),
+            }
+        }
+    }
+    (name, version)
+}
+
+/// The `OnDelete` of the `on_delete` option of `#[belongs_to]` or `#[belongs_none_or]`, the
+/// default is `restrict`
+#[cfg(feature = "rdb")]
+fn on_delete_of(
+    attr: &syn::Attribute,
+    options: &std::collections::HashMap<String, String>,
+    optional: bool,
+) -> proc_macro2::TokenStream {
+    match options.get("on_delete").map(|o| o.as_str()) {
+        None | Some("restrict") => quote!(sewup::rdb::traits::OnDelete::Restrict),
+        Some("cascade") => quote!(sewup::rdb::traits::OnDelete::Cascade),
+        Some("set_none") if optional => quote!(sewup::rdb::traits::OnDelete::SetNone),
+        Some("set_none") => abort!(attr, "on_delete = set_none is only for #[belongs_none_or]"),
+        Some(o) => abort!(
+            attr,
+            "on_delete = {} is not correct, please use cascade, restrict or set_none",
+            o
+        ),
+    }
+}
+
+/// helps you declare the foreign key fields of the relations of `Table`, the field
+/// `{table_name}_id` is added with `#[index]` for `#[belongs_to(table_name)]`, and
+/// `Option<usize>` for `#[belongs_none_or(table_name)]`, if the field is not declared.
+/// This attribute should be placed before `#[derive(Table)]`.
+/// ```compile_fail
+/// #[foreign_keys]
+/// #[derive(Table, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// #[belongs_to(Person)]
+/// pub struct Post {
+///     pub content: SizedString!(50),
+/// }
+///
+/// let post = Post {
+///     content: sewup::types::Raw::from("hello").into(),
+///     person_id: 1,
+/// };
+/// ```
+#[cfg(feature = "rdb")]
+#[proc_macro_attribute]
+pub fn foreign_keys(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    use syn::parse::Parser;
+
+    let mut input = syn::parse_macro_input!(item as syn::ItemStruct);
+    let mut keys = Vec::new();
+    for a in input.attrs.iter() {
+        match relation_of(a) {
+            Some((name, table, _)) if name == "belongs_to" => keys.push((table, quote!(usize))),
+            Some((name, table, _)) if name == "belongs_none_or" => {
+                keys.push((table, quote!(Option<usize>)))
+            }
+            _ => (),
+        }
+    }
+    let fields = match &mut input.fields {
+        syn::Fields::Named(f) => f,
+        _ => abort!(
+            &input.ident,
+            "foreign_keys only use for struct with named fields"
+        ),
+    };
+    for (table, field_type) in keys {
+        let field_name = Ident::new(
+            &format!("{}_id", table.to_ascii_lowercase()),
+            Span::call_site(),
+        );
+        if fields
+            .named
+            .iter()
+            .any(|f| f.ident.as_ref() == Some(&field_name))
+        {
+            continue;
+        }
+        let field = syn::Field::parse_named
+            .parse2(quote!(#[index] pub #field_name: #field_type))
+            .expect("foreign key field is not correct");
+        fields.named.push(field);
+    }
+    quote!(#input).into()
+}
+
 /// helps you setup the test mododule, and test cases in contract.
 /// ```compile_fail
 /// #[ewasm_test]
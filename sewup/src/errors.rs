@@ -13,6 +13,17 @@ pub enum ContractError {
     InsufficientContractInfoError,
 }
 
+#[remain::sorted]
+#[derive(Error, Debug, PartialEq)]
+pub enum EncodingError {
+    #[error("binary is shorter than the length `{0}` in header")]
+    BinaryTruncated(usize),
+    #[error("value can not be encoded in order: `{0}`")]
+    OrderedEncodingFail(String),
+    #[error("header `{0}` is not supported")]
+    UnknownHeader(u8),
+}
+
 #[remain::sorted]
 #[derive(Error, Debug)]
 pub enum HandlerError {
@@ -21,3 +32,16 @@ pub enum HandlerError {
     #[error("Authorization has been refused for current caller's credential")]
     Unauthorized,
 }
+
+#[remain::sorted]
+#[derive(Error, Debug, PartialEq)]
+pub enum StorageError {
+    #[error("the storage loaded as of block `{0}` can not be committed")]
+    CommitPastVersion(u64),
+    #[error("there is no migration registered to upgrade storage from version `{0}`")]
+    MigrationNotRegistered(u8),
+    #[error("the storage as of block `{0}` is not kept in journal")]
+    VersionNotAvailable(i64),
+    #[error("the storage version `{0}` is newer than current library")]
+    VersionNotSupported(u8),
+}
@@ -0,0 +1,83 @@
+//! The header of the binary saved in rows, which is used by the `Key` and `Value` of kv and the
+//! `Record` of rdb.
+//!
+//! The first byte of header is the version of header, and the length of binary is kept in the
+//! following bytes, so the binary of any size can be decoded without guessing the padding.
+//! ```compile_fail
+//! | 1st byte             | 2nd ~ 5th bytes       | ...    | padding                   |
+//! |----------------------|-----------------------|--------|---------------------------|
+//! | HEADER_V1            | length of binary (BE) | Binary | padding to n times Byte32 |
+//! ```
+//!
+//! The rows saved before the versioned header have a one-byte header less than 32, the binary of
+//! these rows is decoded from the 2nd byte, and the padding is ignored by the deserializer.
+use std::convert::TryInto;
+
+use crate::errors::EncodingError;
+
+/// The first byte of the header with the length of binary in 4 bytes
+pub const HEADER_V1: u8 = 0x81;
+
+/// The number of bytes of the header
+pub const HEADER_SIZE: usize = 5;
+
+/// The one-byte headers saved before the versioned header are always less than this
+const LEGACY_HEADER_LIMIT: u8 = 32;
+
+/// Prepend the header to the binary
+pub fn encode(bin: &[u8]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(HEADER_SIZE + bin.len());
+    buffer.push(HEADER_V1);
+    buffer.extend_from_slice(&(bin.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(bin);
+    buffer
+}
+
+/// The binary after the header, the padding is kept for the binary with the legacy header
+pub fn decode(buffer: &[u8]) -> Result<&[u8], EncodingError> {
+    match buffer.first() {
+        Some(&HEADER_V1) if buffer.len() >= HEADER_SIZE => {
+            let length = u32::from_be_bytes(
+                buffer[1..HEADER_SIZE]
+                    .try_into()
+                    .expect("load binary length fail"),
+            ) as usize;
+            buffer
+                .get(HEADER_SIZE..HEADER_SIZE + length)
+                .ok_or(EncodingError::BinaryTruncated(length))
+        }
+        Some(h) if *h < LEGACY_HEADER_LIMIT => Ok(&buffer[1..]),
+        Some(h) => Err(EncodingError::UnknownHeader(*h)),
+        None => Err(EncodingError::BinaryTruncated(0)),
+    }
+}
+
+#[cfg(feature = "default")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_round_trip() {
+        let bin = vec![7u8; 100];
+        let mut buffer = encode(&bin);
+        buffer.resize(128, 0);
+        assert_eq!(decode(&buffer).unwrap(), bin.as_slice());
+        assert_eq!(decode(&encode(&[])).unwrap(), &[] as &[u8]);
+        assert_eq!(
+            decode(&buffer[0..50]),
+            Err(EncodingError::BinaryTruncated(100))
+        );
+        assert_eq!(decode(&[0x42]), Err(EncodingError::UnknownHeader(0x42)));
+    }
+
+    #[test]
+    fn test_decode_legacy_header() {
+        let bin = bincode::serialize(&(1u64, "legacy".to_string())).unwrap();
+        let mut buffer = vec![((bin.len() + 1) & 31) as u8];
+        buffer.extend_from_slice(&bin);
+        buffer.resize(64, 0);
+        let (n, s): (u64, String) = bincode::deserialize(decode(&buffer).unwrap()).unwrap();
+        assert_eq!((n, s.as_str()), (1, "legacy"));
+    }
+}
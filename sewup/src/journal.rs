@@ -0,0 +1,209 @@
+//! Journal keeps the change log of the storage when the `Versioning` feature of kv or rdb is
+//! enabled, such that the storage can be loaded as of an earlier block.
+//!
+//! Each commit appends an entry keyed by the block number, which records the previous values of
+//! the slots changed by the commit.  The storage as of block `h` is the latest storage with the
+//! previous values of the entries committed after block `h`.
+//!
+//! The journal is saved in the slots addressed by sha3 hash, so it never overlaps the slots
+//! indexed from the header of kv or rdb.
+//! ```compile_fail
+//! | address                      | 0th ~ 7th         | ... | 28th ~ 31st               |
+//! |------------------------------|-------------------|-----|---------------------------|
+//! | sha3("journal")              | -                 | -   | number of entries (BE)    |
+//! | sha3("journal", i)           | block number (BE) | -   | number of changes (BE)    |
+//! | sha3("journal", i, j, 0)     | the address of the j-th changed slot of i-th entry       |
+//! | sha3("journal", i, j, 1)     | the previous value of the j-th changed slot of i-th entry |
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+
+use anyhow::Result;
+
+use crate::errors::StorageError;
+use crate::storage::{block_number, load as storage_load, store as storage_store};
+use crate::utils::sha3_256;
+
+const JOURNAL_PREFIX: &[u8] = b"journal";
+
+fn journal_addr(path: &[u32]) -> [u8; 32] {
+    let mut input: Vec<u8> = JOURNAL_PREFIX.into();
+    for p in path.iter() {
+        input.extend_from_slice(&p.to_be_bytes());
+    }
+    sha3_256(&input)
+}
+
+/// The storage as of a specific block, which is the latest storage overlaid with the previous
+/// values of the slots changed after the block.
+#[derive(Default)]
+pub struct Snapshot {
+    block_height: Option<u64>,
+    changes: HashMap<[u8; 32], [u8; 32]>,
+}
+
+impl Snapshot {
+    /// The snapshot of latest storage
+    pub fn latest() -> Self {
+        Self::default()
+    }
+
+    /// The block height of snapshot, `None` for the latest storage
+    pub fn block_height(&self) -> Option<u64> {
+        self.block_height
+    }
+
+    /// Build the snapshot of storage as of the block height
+    /// The storage before the first entry is rebuilt from the previous values of all the
+    /// entries, and it is not available if the journal is never kept.
+    pub fn at(block_height: i64) -> Result<Self> {
+        let height: u64 = block_height
+            .try_into()
+            .map_err(|_| StorageError::VersionNotAvailable(block_height))?;
+        let length = entry_length();
+        if length == 0 {
+            return Err(StorageError::VersionNotAvailable(block_height).into());
+        }
+
+        let mut reverted = Vec::new();
+        for i in (0..length).rev() {
+            let (block, change_length) = entry_header(i);
+            if block <= height {
+                break;
+            }
+            reverted.push((i, change_length));
+        }
+
+        // The entries are reverted from the latest one, so the value before the first change
+        // after the block height is kept.
+        let mut changes = HashMap::new();
+        for (i, change_length) in reverted {
+            for j in 0..change_length {
+                let addr = storage_load(&journal_addr(&[i, j, 0]));
+                let previous = storage_load(&journal_addr(&[i, j, 1]));
+                changes.insert(addr, previous);
+            }
+        }
+
+        Ok(Self {
+            block_height: Some(height),
+            changes,
+        })
+    }
+
+    /// Load the slot as of the block of snapshot
+    pub fn load(&self, addr: &[u8; 32]) -> [u8; 32] {
+        if let Some(value) = self.changes.get(addr) {
+            *value
+        } else {
+            storage_load(addr)
+        }
+    }
+}
+
+/// The change log of a commit, the previous values are recorded only if the versioning is
+/// enabled, and the change log should be committed after all the slots are stored.
+pub struct ChangeLog {
+    enabled: bool,
+    previous: BTreeMap<[u8; 32], [u8; 32]>,
+}
+
+impl ChangeLog {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            previous: BTreeMap::new(),
+        }
+    }
+
+    /// Keep the first previous value of the slot in this commit, if the value is changed
+    fn record(&mut self, addr: &[u8; 32], previous: [u8; 32], value: &[u8; 32]) {
+        if previous != *value && !self.previous.contains_key(addr) {
+            self.previous.insert(*addr, previous);
+        }
+    }
+
+    /// Store the value into the slot, and record the previous value
+    /// The slot is not written if the value is not changed.
+    pub fn store(&mut self, addr: &[u8; 32], value: &[u8; 32]) {
+        let previous = storage_load(addr);
+        if previous == *value {
+            return;
+        }
+        if self.enabled {
+            self.record(addr, previous, value);
+        }
+        storage_store(addr, value);
+    }
+
+    /// Append the change log as an entry of journal with current block number
+    pub fn commit(self) {
+        if !self.enabled || self.previous.is_empty() {
+            return;
+        }
+        let i = entry_length();
+        let mut header = [0u8; 32];
+        header[0..8].copy_from_slice(&block_number().to_be_bytes());
+        header[28..32].copy_from_slice(&(self.previous.len() as u32).to_be_bytes());
+        storage_store(&journal_addr(&[i]), &header);
+
+        for (j, (addr, previous)) in self.previous.iter().enumerate() {
+            storage_store(&journal_addr(&[i, j as u32, 0]), addr);
+            storage_store(&journal_addr(&[i, j as u32, 1]), previous);
+        }
+
+        let mut length = [0u8; 32];
+        length[28..32].copy_from_slice(&(i + 1).to_be_bytes());
+        storage_store(&journal_addr(&[]), &length);
+    }
+}
+
+fn entry_length() -> u32 {
+    let buffer = storage_load(&journal_addr(&[]));
+    u32::from_be_bytes(buffer[28..32].try_into().expect("load journal length fail"))
+}
+
+/// Returns the block number and the number of changes of the i-th entry
+fn entry_header(i: u32) -> (u64, u32) {
+    let buffer = storage_load(&journal_addr(&[i]));
+    (
+        u64::from_be_bytes(buffer[0..8].try_into().expect("load block number fail")),
+        u32::from_be_bytes(buffer[28..32].try_into().expect("load change length fail")),
+    )
+}
+
+/// List the block heights of the versions kept in the journal
+pub fn versions() -> Vec<u64> {
+    let mut output: Vec<u64> = Vec::new();
+    for i in 0..entry_length() {
+        let (block, _) = entry_header(i);
+        if output.last() != Some(&block) {
+            output.push(block);
+        }
+    }
+    output
+}
+
+#[cfg(feature = "default")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_log_keeps_first_previous_value() {
+        let mut log = ChangeLog::new(true);
+        let addr = [1u8; 32];
+        log.record(&addr, [0u8; 32], &[2u8; 32]);
+        log.record(&addr, [2u8; 32], &[3u8; 32]);
+        log.record(&[4u8; 32], [5u8; 32], &[5u8; 32]);
+        assert_eq!(log.previous.len(), 1);
+        assert_eq!(log.previous.get(&addr), Some(&[0u8; 32]));
+    }
+
+    #[test]
+    fn test_journal_addr() {
+        assert_ne!(journal_addr(&[0]), journal_addr(&[]));
+        assert_ne!(journal_addr(&[0, 1, 0]), journal_addr(&[0, 1, 1]));
+        assert_ne!(journal_addr(&[1, 0, 0]), journal_addr(&[0, 1, 0]));
+    }
+}
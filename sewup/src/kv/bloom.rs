@@ -0,0 +1,97 @@
+//! Bloom filter helps the bucket to skip the scanning of hash keys when the key is absent.
+//! The filter is sized per bucket, and saved with the `RawBucket` into the store.
+use std::convert::TryInto;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// The bits reserved for each item, about 1% false positive rate with 3 hash functions
+const BITS_PER_ITEM: usize = 10;
+
+/// The number of hash functions, each one takes 8 bytes from the 24 bytes hash of key
+const HASH_NUMBER: usize = 3;
+
+/// The filter is allocated by the size of `Raw`, such that it is easy to store on chain
+const BYTES_PER_BLOCK: usize = 32;
+
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+}
+
+impl BloomFilter {
+    /// New a bloom filter which can hold `capacity` items with low false positive rate
+    pub fn with_capacity(capacity: usize) -> Self {
+        let bytes = (capacity * BITS_PER_ITEM + 7) / 8;
+        let blocks = (bytes + BYTES_PER_BLOCK - 1) / BYTES_PER_BLOCK;
+        Self {
+            bits: vec![0u8; blocks.max(1) * BYTES_PER_BLOCK],
+        }
+    }
+
+    /// Build a bloom filter from the hashes of keys
+    pub fn from_hashes<'a, I>(hashes: I, len: usize) -> Self
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let mut filter = Self::with_capacity(len * 2);
+        for hash in hashes {
+            filter.set_bits(hash);
+        }
+        filter
+    }
+
+    /// Load the filter from the bytes saved on chain
+    pub(crate) fn from_bytes(bits: Vec<u8>) -> Self {
+        Self { bits }
+    }
+
+    /// The bytes of filter, which are saved on chain
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    /// The number of items the filter designed for
+    pub fn capacity(&self) -> usize {
+        self.bits.len() * 8 / BITS_PER_ITEM
+    }
+
+    /// Add the hash of key into the filter
+    pub fn insert(&mut self, hash: &[u8; 24]) {
+        if self.bits.is_empty() {
+            self.bits = vec![0u8; BYTES_PER_BLOCK];
+        }
+        self.set_bits(hash);
+    }
+
+    /// Check the hash of key may be in the filter, there is no false negative
+    pub fn may_contain(&self, hash: &[u8; 24]) -> bool {
+        if self.bits.is_empty() {
+            return false;
+        }
+        for i in self.indexes(hash) {
+            if self.bits[i / 8] & (1 << (i % 8)) == 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn set_bits(&mut self, hash: &[u8]) {
+        for i in self.indexes(hash) {
+            self.bits[i / 8] |= 1 << (i % 8);
+        }
+    }
+
+    fn indexes(&self, hash: &[u8]) -> Vec<usize> {
+        let bit_length = (self.bits.len() * 8) as u64;
+        (0..HASH_NUMBER)
+            .map(|n| {
+                let part: [u8; 8] = hash[n * 8..(n + 1) * 8]
+                    .try_into()
+                    .expect("hash of key should be 24 bytes");
+                (u64::from_be_bytes(part) % bit_length) as usize
+            })
+            .collect()
+    }
+}
@@ -3,18 +3,19 @@
 //! Items save into bucket may have different encoding, the will base on the feature you enabled.
 use std::cmp::PartialEq;
 use std::marker::PhantomData;
-use std::ops::Range;
+use std::ops::{Bound, Range, RangeBounds};
 
 use anyhow::Result;
 
+use super::bloom::BloomFilter;
 use super::traits::{Key, Value, VecLike};
 use crate::kv::traits::key::AsHashKey;
 use crate::types::{Raw, Row};
 
 /// `RawBucket` is a structure the data format really store
 /// The hash key is stored in the first item, and the `Key` and `Value` are
-/// stored in the second item
-pub type RawBucket = (Vec<Raw>, Vec<Raw>);
+/// stored in the second item, and the bloom filter of the hash keys is in the third item
+pub type RawBucket = (Vec<Raw>, Vec<Raw>, BloomFilter);
 
 /// Bucket is a wrapper for `RawBucket`, including the name of the bucket
 pub struct Bucket<K: Key, V: Value> {
@@ -73,6 +74,63 @@ impl<'a, K: Key, V: Value> Iterator for Iter<'a, K, V> {
     }
 }
 
+/// The iterator over the items in the order of keys, and it is also a `DoubleEndedIterator`,
+/// so you can use `rev()` to iterate the items in reverse order.
+/// The order of keys is base on the order-preserving encoding `Key::to_ordered_bytes`.
+pub struct OrderedIter<'a, K, V> {
+    raw_bucket: &'a RawBucket,
+    /// The position and sizes of item, (item_idx, k_size, v_size)
+    items: Vec<(u32, u32, u32)>,
+    front: usize,
+    back: usize,
+    phantom_k: PhantomData<K>,
+    phantom_v: PhantomData<V>,
+}
+
+impl<'a, K: Key, V: Value> OrderedIter<'a, K, V> {
+    fn read_item(&self, position: usize) -> Item<K, V> {
+        let (item_idx, k_size, v_size) = self.items[position];
+        let mut key_row =
+            Row::from(&self.raw_bucket.1[item_idx as usize..(item_idx + k_size) as usize]);
+        key_row.make_buffer();
+        let key = K::from_row_key(&key_row).expect("parse key from raw fail");
+
+        let mut value_row = Row::from(
+            &self.raw_bucket.1[(item_idx + k_size) as usize..(item_idx + k_size + v_size) as usize],
+        );
+        value_row.make_buffer();
+        let value = V::from_row_value(&value_row).expect("parse value from raw fail");
+        (key, value)
+    }
+}
+
+impl<'a, K: Key, V: Value> Iterator for OrderedIter<'a, K, V> {
+    type Item = Item<K, V>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.read_item(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.back - self.front, Some(self.back - self.front))
+    }
+}
+
+impl<'a, K: Key, V: Value> DoubleEndedIterator for OrderedIter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.read_item(self.back))
+    }
+}
+
+impl<'a, K: Key, V: Value> ExactSizeIterator for OrderedIter<'a, K, V> {}
+
 impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
     /// New a `Bucket` with name
     pub fn new(name: String, raw_bucket: RawBucket) -> Bucket<K, V> {
@@ -85,8 +143,15 @@ impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
     }
 
     fn bloom_filter(&self, hash: &[u8; 24]) -> bool {
-        // TODO: bloom filter here
-        true
+        self.raw_bucket.2.may_contain(hash)
+    }
+
+    /// Rebuild the bloom filter from the hash keys, the filter will be resized with the bucket
+    fn rebuild_bloom_filter(&mut self) {
+        self.raw_bucket.2 = BloomFilter::from_hashes(
+            self.raw_bucket.0.iter().map(|h| &h.bytes[0..24]),
+            self.raw_bucket.0.len(),
+        );
     }
 
     /// Check the `Key` in the bucket
@@ -110,6 +175,10 @@ impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
     pub fn get(&self, key: K) -> Result<Option<V>> {
         let hash = key.gen_hash()?;
 
+        if !self.bloom_filter(&hash) {
+            return Ok(None);
+        }
+
         let mut idx = 0u32;
 
         for item in self.raw_bucket.0.iter() {
@@ -158,6 +227,12 @@ impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
         self.raw_bucket.1.append(&mut row_key);
         self.raw_bucket.1.append(&mut value);
 
+        if self.raw_bucket.0.len() > self.raw_bucket.2.capacity() {
+            self.rebuild_bloom_filter();
+        } else {
+            self.raw_bucket.2.insert(&hash);
+        }
+
         Ok(())
     }
 
@@ -165,6 +240,10 @@ impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
     pub fn remove(&mut self, key: K) -> Result<()> {
         let hash = key.gen_hash()?;
 
+        if !self.bloom_filter(&hash) {
+            return Ok(());
+        }
+
         let mut idx = 0u32;
 
         let mut matched: Option<usize> = None;
@@ -181,6 +260,7 @@ impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
         }
         if let Some(i) = matched {
             self.raw_bucket.0.remove(i);
+            self.rebuild_bloom_filter();
         }
         Ok(())
     }
@@ -197,7 +277,8 @@ impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
         };
     }
 
-    /// Iterate the items in the bucket with specific range
+    /// Iterate the items in the bucket with specific range of the insertion positions,
+    /// use `range` to iterate the items with a range of keys
     pub fn iter_range(&self, start: usize, end: usize) -> Iter<K, V> {
         return Iter {
             raw_bucket: &self.raw_bucket,
@@ -209,9 +290,68 @@ impl<'a, K: Key + PartialEq, V: Clone + Value> Bucket<K, V> {
         };
     }
 
-    /// Iterate the times with special prefix
-    pub fn iter_prefix(&self, prefix: K) -> Iter<K, V> {
-        unimplemented!();
+    /// Collect the items whose ordered key bytes pass the filter, and sort them by the key
+    fn ordered_items<F>(&self, filter: F) -> OrderedIter<'_, K, V>
+    where
+        F: Fn(&[u8]) -> bool,
+    {
+        let mut items: Vec<(Vec<u8>, (u32, u32, u32))> = Vec::new();
+        let mut item_idx = 0u32;
+        for hash_key in self.raw_bucket.0.iter() {
+            let (k_size, v_size) = hash_key.get_size();
+            let mut key_row =
+                Row::from(&self.raw_bucket.1[item_idx as usize..(item_idx + k_size) as usize]);
+            key_row.make_buffer();
+            let key = K::from_row_key(&key_row).expect("parse key from raw fail");
+            let ordered_bytes = key.to_ordered_bytes().expect("encode key in order fail");
+            if filter(&ordered_bytes) {
+                items.push((ordered_bytes, (item_idx, k_size, v_size)));
+            }
+            item_idx = item_idx + k_size + v_size;
+        }
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let items: Vec<(u32, u32, u32)> = items.into_iter().map(|(_, i)| i).collect();
+        OrderedIter {
+            raw_bucket: &self.raw_bucket,
+            back: items.len(),
+            items,
+            front: 0,
+            phantom_k: PhantomData,
+            phantom_v: PhantomData,
+        }
+    }
+
+    /// Iterate all the items in the order of keys
+    pub fn iter_ordered(&self) -> OrderedIter<'_, K, V> {
+        self.ordered_items(|_| true)
+    }
+
+    /// Iterate the items with special prefix in the order of keys
+    /// The prefix can be a different type from the key, for example, a `String` prefix for the
+    /// key of structure starting with a `String` field.
+    pub fn iter_prefix<P: Key>(&self, prefix: P) -> OrderedIter<'_, K, V> {
+        let prefix = prefix
+            .to_ordered_bytes()
+            .expect("encode prefix in order fail");
+        self.ordered_items(|bytes| bytes.starts_with(&prefix))
+    }
+
+    /// Iterate the items in the range of keys in the order of keys
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> OrderedIter<'_, K, V> {
+        let encode = |bound: Bound<&K>| -> Bound<Vec<u8>> {
+            match bound {
+                Bound::Included(k) => {
+                    Bound::Included(k.to_ordered_bytes().expect("encode bound in order fail"))
+                }
+                Bound::Excluded(k) => {
+                    Bound::Excluded(k.to_ordered_bytes().expect("encode bound in order fail"))
+                }
+                Bound::Unbounded => Bound::Unbounded,
+            }
+        };
+        let bounds = (encode(range.start_bound()), encode(range.end_bound()));
+        self.ordered_items(|bytes| bounds.contains(&bytes.to_vec()))
     }
 
     // Always watch a Item among the blocks
@@ -372,7 +512,7 @@ pub type SewUpVec<T> = super::bucket::Bucket<usize, T>;
 
 impl<V: Clone + Value> SewUpVec<V> {
     fn from_vec(&mut self, v: Vec<V>) {
-        *self = super::bucket::Bucket::<usize, V>::new(self.name.clone(), (Vec::new(), Vec::new()));
+        *self = super::bucket::Bucket::<usize, V>::new(self.name.clone(), Default::default());
         v.into_iter().enumerate().map(|(i, v)| self.set(i, v));
     }
 }
@@ -406,7 +546,7 @@ impl<V: Clone + Value + PartialEq> VecLike<V> for SewUpVec<V> {
     }
 
     fn clear(&mut self) {
-        *self = super::bucket::Bucket::<usize, V>::new(self.name.clone(), (Vec::new(), Vec::new()));
+        *self = super::bucket::Bucket::<usize, V>::new(self.name.clone(), Default::default());
     }
 
     fn resize_with<F>(&mut self, new_len: usize, mut f: F)
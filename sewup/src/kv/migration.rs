@@ -0,0 +1,54 @@
+//! The upgrade steps of the storage format of kv
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::kv::{BloomFilter, RawBucket};
+use crate::migration::Migration;
+use crate::types::Raw;
+
+/// The upgrade steps for the binary of buckets saved by the previous versions
+pub(crate) const MIGRATIONS: &[Migration<Vec<u8>>] = &[
+    Migration {
+        from: 0,
+        upgrade: add_bloom_filter,
+    },
+    Migration {
+        from: 1,
+        upgrade: save_in_bucket_slots,
+    },
+    Migration {
+        from: 2,
+        upgrade: save_in_namespace,
+    },
+];
+
+/// The buckets of version 0 are saved without bloom filter
+fn add_bloom_filter(bin: Vec<u8>) -> Result<Vec<u8>> {
+    let tenants: HashMap<String, Option<(Vec<Raw>, Vec<Raw>)>> = bincode::deserialize(&bin)?;
+    let tenants: HashMap<String, Option<RawBucket>> = tenants
+        .into_iter()
+        .map(|(name, bucket)| {
+            let bucket = bucket.map(|(hashes, items)| {
+                let filter =
+                    BloomFilter::from_hashes(hashes.iter().map(|h| &h.bytes[0..24]), hashes.len());
+                (hashes, items, filter)
+            });
+            (name, bucket)
+        })
+        .collect();
+    Ok(bincode::serialize(&tenants)?)
+}
+
+/// The binary of buckets is not changed in version 2, but the buckets are saved in their own
+/// slots when the store is committed, and the slots of the previous binary are cleared.
+fn save_in_bucket_slots(bin: Vec<u8>) -> Result<Vec<u8>> {
+    Ok(bin)
+}
+
+/// The binary of buckets is not changed in version 3, but the header and the bucket directory
+/// are saved in the namespace of kv when the store is committed, and the slots in the root space
+/// are cleared.
+fn save_in_namespace(bin: Vec<u8>) -> Result<Vec<u8>> {
+    Ok(bin)
+}
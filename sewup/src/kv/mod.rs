@@ -2,7 +2,7 @@
 //! It aims to be lightweight and with a nice high level interface.
 //! Store is an abstract storage instance from one account in one block
 //! There can be more than one bucket in a store.
-//! Besides, store can improt data from the specific block.
+//! Besides, store can improt data from the specific block, if the `Versioning` feature is enabled.
 //! Different bucket can defined different kind of key value storage pair.
 //!
 //! Please check out the structure `kv::Store` and `kv::Bucket` to learn more about this.
@@ -28,27 +28,45 @@
 //! let mut bucket2 = store.bucket::<Raw, Row>("bucket2").unwrap();
 //! bucket2.set(b"long".into(), "Testing".to_string().into());
 //! ```
+//!
+//! ## Import data from a specific block
+//!
+//! The change log of each commit is kept with the block number when the `Versioning` feature is
+//! enabled, and the store can be imported as of the blocks listed by `Store::versions()`.
+//! ```ignore
+//! use sewup::kv::{Feature, Store};
+//!
+//! let mut store = Store::new().unwrap();
+//! store.enable_feature(Feature::Versioning);
+//! store.commit().unwrap();
+//!
+//! let block_height = Store::versions().unwrap()[0];
+//! let old_store = Store::load(Some(block_height as i64)).unwrap();
+//! ```
 
 #[cfg_attr(any(feature = "debug", test), derive(Debug))]
 #[derive(PartialEq)]
 pub enum Feature {
     Default = 1,
+    /// Keep the change log of commits, such that the store can be loaded as of an earlier block
+    Versioning = 2,
 }
 
 #[allow(unused_variables)]
 #[allow(dead_code)]
-#[cfg(target_arch = "wasm32")]
 mod store;
-#[cfg(target_arch = "wasm32")]
 pub use store::*;
-#[cfg(not(target_arch = "wasm32"))]
-pub struct Store {}
 
 #[allow(unused_variables)]
 #[allow(dead_code)]
 mod bucket;
 pub use bucket::*;
 
+mod bloom;
+pub use bloom::*;
+
+mod migration;
+
 #[cfg(test)]
 mod tests;
 
@@ -1,45 +1,91 @@
 use std::cmp::PartialEq;
-use std::collections::hash_map::HashMap;
+use std::collections::{hash_map::HashMap, BTreeMap};
 use std::convert::TryInto;
 
+use crate::errors::StorageError;
+use crate::journal::{self, ChangeLog, Snapshot};
 use crate::kv::{
     errors::Error,
     traits::{Key, Value},
     Feature,
 };
-use crate::utils::storage_index_to_addr;
+use crate::migration::upgrade;
+use crate::storage::{Namespace, KV_NAMESPACE};
+use crate::types::Raw;
+use crate::utils::sha3_256;
 
+use super::bloom::BloomFilter;
 use super::bucket::{Bucket, RawBucket, SewUpVec};
+use super::migration::MIGRATIONS;
 use anyhow::Result;
-use ewasm_api::{storage_load, storage_store};
 
 const KV_FEATURE: u8 = 0;
-const VERSION: u8 = 0;
-const CONFIG_ADDR: [u8; 32] = [0; 32];
+const VERSION: u8 = 3;
+
+/// The store is saved as a binary of all buckets before version 2
+const SLOT_LAYOUT_VERSION: u8 = 2;
 
 type Tenants = HashMap<String, Option<RawBucket>>;
 
+/// The number of slots of hash keys, items and bloom filter of a bucket
+type BucketSize = (u32, u32, u32);
+
+/// The parts of a bucket are saved in different slot spaces, such that changing or appending an
+/// item will not move the slots of other items
+const HASH_KEY_PART: u8 = 0;
+const ITEM_PART: u8 = 1;
+const BLOOM_FILTER_PART: u8 = 2;
+
+/// The address of the slot in the part of bucket
+fn bucket_addr(name: &str, part: u8, idx: u32) -> [u8; 32] {
+    let mut input: Vec<u8> = "bucket".as_bytes().into();
+    input.extend_from_slice(&(name.len() as u32).to_be_bytes());
+    input.extend_from_slice(name.as_bytes());
+    input.push(part);
+    input.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&input)
+}
+
 /// Store is a storage space for an account in a specific block.
 /// We can import the storage from a past block, and we only commit the storage
 /// into the latest block.
+/// The past blocks can be imported only if the `Versioning` feature is enabled before the
+/// commits of the blocks, and `Store::versions()` lists the blocks can be imported.
 ///
 /// Besides, there may be more than one bucket in store, such that you can
 /// easily save different kind of key/value pair in the chain.
 ///
 /// ### Store Header
-/// The fist 32 bytes are reserved as header of the store,
+/// The 0th slot in the namespace of kv is reserved as header of the store,
 ///
 /// | 0th            | 1st          | 2nd ~ 3rd        | ... | 28th ~ 31st |
 /// |----------------|--------------|------------------|-----|-------------|
 /// | Sewup Features | version (BE) | KV Features (LE) | -   | size (BE)   |
 ///
 /// Base on the features, the storage may have different encoding in to binary
+///
+/// ### Store Layout
+/// The size in header is the size of the bucket directory, which is the binary of bucket names
+/// and the numbers of slots of the buckets, and saved from the 1st slot in the namespace of kv.
+/// The hash keys, items and bloom filter of a bucket are saved in the slots addressed by the
+/// hash of bucket name, part and index, such that a commit only writes the slots changed since
+/// the store loaded, and clears the slots not used any more.
+/// Only the bucket directory is loaded with the store, and a bucket is loaded when it is opened,
+/// so the cost of a call depends on the buckets it uses.
 pub struct Store {
     _sewup_feature: u8,
     version: u8,
     _features: u16,
     _size: u32,
     tenants: Tenants,
+    /// The buckets in storage which are not loaded yet
+    directory: HashMap<String, BucketSize>,
+    /// The namespace the store loaded from, the store is always committed into the namespace of
+    /// kv
+    namespace: Namespace,
+    snapshot: Snapshot,
+    /// The slots read from the latest storage when loading
+    loaded: HashMap<[u8; 32], [u8; 32]>,
 }
 
 impl Default for Store {
@@ -53,6 +99,10 @@ impl Default for Store {
             _features,
             _size: 0,
             tenants: Tenants::default(),
+            directory: HashMap::new(),
+            namespace: Namespace::new(KV_NAMESPACE),
+            snapshot: Snapshot::latest(),
+            loaded: HashMap::new(),
         }
     }
 }
@@ -74,33 +124,67 @@ impl Store {
         if self._features & Feature::Default as u16 > 0 {
             output.push(Feature::Default)
         }
+        if self._features & Feature::Versioning as u16 > 0 {
+            output.push(Feature::Versioning)
+        }
         output
     }
 
+    /// Enable the feature of storage, the feature will be saved after commit
+    pub fn enable_feature(&mut self, feature: Feature) {
+        self._features |= feature as u16;
+    }
+
+    /// The block heights of the versions kept in the journal, which can be imported by `load`
+    pub fn versions() -> Result<Vec<u64>> {
+        Ok(journal::versions())
+    }
+
     /// Get a list of bucket names
     pub fn buckets(&self) -> Vec<String> {
-        self.tenants.keys().map(|k| k.to_string()).collect()
+        self.tenants
+            .keys()
+            .chain(self.directory.keys())
+            .map(|k| k.to_string())
+            .collect()
     }
 
     pub fn bucket<'a, K: Key + PartialEq, V: Default + Clone + Value>(
         &mut self,
         name: &str,
     ) -> Result<Bucket<K, V>> {
-        let raw_bucket = if self.tenants.contains_key(name) {
+        Ok(Bucket::new(name.into(), self.take_raw_bucket(name)?))
+    }
+
+    /// Take the raw bucket out from the store, the bucket will be loaded from storage if it is
+    /// not loaded, or be created if it does not exist.
+    fn take_raw_bucket(&mut self, name: &str) -> Result<RawBucket> {
+        if let Some(size) = self.directory.remove(name) {
+            let snapshot = std::mem::take(&mut self.snapshot);
+            let raw_bucket = self.load_bucket(&snapshot, name, size);
+            self.snapshot = snapshot;
+            self.tenants.insert(name.into(), None);
+            Ok(raw_bucket)
+        } else if self.tenants.contains_key(name) {
             if let Some(bucket) = self.tenants.get_mut(name).unwrap().take() {
-                bucket
+                Ok(bucket)
             } else {
-                return Err(Error::BucketAlreadyOpen.into());
+                Err(Error::BucketAlreadyOpen.into())
             }
         } else {
             self.tenants.insert(name.into(), None);
-            (Vec::new(), Vec::new())
-        };
-        Ok(Bucket::new(name.into(), raw_bucket))
+            Ok((Vec::new(), Vec::new(), BloomFilter::default()))
+        }
     }
 
     pub fn drop_bucket<S: AsRef<str>>(&mut self, name: S) -> Result<()> {
         let name = name.as_ref().to_string();
+        if let Some(size) = self.directory.remove(&name) {
+            // Load the slots of the bucket, such that they will be cleared when committing
+            let snapshot = std::mem::take(&mut self.snapshot);
+            self.load_bucket(&snapshot, &name, size);
+            self.snapshot = snapshot;
+        }
         self.tenants.remove(&name);
         Ok(())
     }
@@ -111,20 +195,10 @@ impl Store {
     ///
     /// ```
     /// let mut storage = sewup::kv::Store::new().unwrap();
-    /// let v = storage.vec::<SimpleStruct>("vector1").unwrap();
+    /// let v = storage.vec::<usize>("vector1").unwrap();
     /// ```
     pub fn vec<'a, V: Default + Clone + Value>(&mut self, name: &str) -> Result<SewUpVec<V>> {
-        let raw_bucket = if self.tenants.contains_key(name) {
-            if let Some(bucket) = self.tenants.get_mut(name).unwrap().take() {
-                bucket
-            } else {
-                return Err(Error::BucketAlreadyOpen.into());
-            }
-        } else {
-            self.tenants.insert(name.into(), None);
-            (Vec::new(), Vec::new())
-        };
-        Ok(Bucket::new(name.into(), raw_bucket))
+        Ok(Bucket::new(name.into(), self.take_raw_bucket(name)?))
     }
 
     /// Returns the size on load
@@ -132,7 +206,7 @@ impl Store {
         self._size
     }
 
-    /// Returns the size in bytes
+    /// Returns the size in bytes of the loaded buckets
     pub fn size(&self) -> Result<u32> {
         let len =
             bincode::serialized_size(&self.tenants).expect("estimate serialized db size fail");
@@ -141,45 +215,129 @@ impl Store {
 
     /// Import the database from the specific block height
     /// If not the will import db from the latest block
+    ///
+    /// The storage saved in previous version will be upgraded, and the upgraded storage is
+    /// committed back if it is loaded from the latest block.
+    ///
+    /// All the buckets of a past block are loaded, and committing the store loaded from a past
+    /// block writes the whole store as of the block over the latest storage, that is, the store
+    /// is rolled back to the block.
     pub fn load(block_height: Option<i64>) -> Result<Self> {
-        if let Some(_block_height) = block_height {
-            unimplemented!();
+        let snapshot = if let Some(block_height) = block_height {
+            Snapshot::at(block_height)?
         } else {
-            let mut store = Self::new()?;
+            Snapshot::latest()
+        };
+        let mut store = Self::new()?;
 
-            let config: [u8; 32] = storage_load(&CONFIG_ADDR.into()).bytes;
+        let config: [u8; 32] = if let Some(config) = store.load_header(&snapshot) {
+            config
+        } else {
+            store.snapshot = snapshot;
+            return Ok(store);
+        };
 
-            if KV_FEATURE != config[0] {
-                panic!("Sewup feature not correct")
-            }
+        store._features =
+            u16::from_le_bytes(config[2..4].try_into().expect("load storage feature fail"));
+        store._size = u32::from_be_bytes(config[28..32].try_into().expect("load db length fail"));
 
-            if VERSION != config[1] {
-                // TODO
-                panic!("migration not implement")
+        if config[1] < SLOT_LAYOUT_VERSION {
+            let mut bin = store.load_bytes(&snapshot, store._size / 32 + 1);
+            bin = upgrade(MIGRATIONS, config[1], VERSION, bin)?;
+            store.tenants = bincode::deserialize(&bin).expect("load db binary fail");
+        } else if config[1] > VERSION {
+            return Err(StorageError::VersionNotSupported(config[1]).into());
+        } else {
+            let bin = store.load_bytes(&snapshot, (store._size + 31) / 32);
+            let directory: Vec<(String, BucketSize)> =
+                bincode::deserialize(&bin).expect("load bucket directory fail");
+            if block_height.is_some() {
+                // The buckets of a past block are all loaded, such that the store can be
+                // committed back as a whole
+                for (name, size) in directory.into_iter() {
+                    let raw_bucket = store.load_bucket(&snapshot, &name, size);
+                    store.tenants.insert(name, Some(raw_bucket));
+                }
+            } else {
+                store.directory = directory.into_iter().collect();
             }
+        }
 
-            store._features =
-                u16::from_le_bytes(config[2..4].try_into().expect("load storage feature fail"));
+        if VERSION != config[1] && block_height.is_none() {
+            store._size = store.commit()?;
+        }
+        store.snapshot = snapshot;
 
-            let mut bin: Vec<u8> = Vec::new();
-            let mut addr: [u8; 32] = [0; 32];
-            let mut storage_index = 0;
-            store._size =
-                u32::from_be_bytes(config[28..32].try_into().expect("load db length fail"));
+        Ok(store)
+    }
 
-            for i in 0..(store._size / 32) {
-                storage_index += 1;
-                storage_index_to_addr(storage_index, &mut addr);
-                let buffer: [u8; 32] = storage_load(&addr.into()).bytes;
-                bin.extend_from_slice(&buffer);
+    /// Find the header in the namespace of kv, or in the root space if the store is saved by the
+    /// previous version, and returns `None` if the store is never committed
+    fn load_header(&mut self, snapshot: &Snapshot) -> Option<[u8; 32]> {
+        for namespace in [Namespace::new(KV_NAMESPACE), Namespace::root()] {
+            let addr = namespace.index_addr(0);
+            let config = snapshot.load(&addr);
+            if config != [0u8; 32] && config[0] == KV_FEATURE {
+                self.namespace = namespace;
+                self.keep_slot(snapshot, &addr, config);
+                return Some(config);
             }
-            storage_index += 1;
-            storage_index_to_addr(storage_index, &mut addr);
-            let buffer: [u8; 32] = storage_load(&addr.into()).bytes;
+        }
+        None
+    }
+
+    /// Load a slot from the snapshot
+    fn load_slot(&mut self, snapshot: &Snapshot, addr: &[u8; 32]) -> [u8; 32] {
+        let value = snapshot.load(addr);
+        self.keep_slot(snapshot, addr, value);
+        value
+    }
+
+    /// Keep the value of slot if it is the latest one, such that the slot will not be written
+    /// again if it is not changed, or be cleared if it is not used when committing
+    fn keep_slot(&mut self, snapshot: &Snapshot, addr: &[u8; 32], value: [u8; 32]) {
+        if snapshot.block_height().is_none() {
+            self.loaded.insert(*addr, value);
+        }
+    }
+
+    /// Load the binary saved from the 1st slot
+    fn load_bytes(&mut self, snapshot: &Snapshot, slot_length: u32) -> Vec<u8> {
+        let mut bin: Vec<u8> = Vec::new();
+        for storage_index in 1..=slot_length as usize {
+            let addr = self.namespace.index_addr(storage_index);
+            let buffer: [u8; 32] = self.load_slot(snapshot, &addr);
             bin.extend_from_slice(&buffer);
-            store.tenants = bincode::deserialize(&bin).expect("load db binary fail");
+        }
+        bin
+    }
 
-            Ok(store)
+    fn load_bucket(&mut self, snapshot: &Snapshot, name: &str, size: BucketSize) -> RawBucket {
+        let (hash_length, item_length, filter_length) = size;
+        let mut load_part = |part: u8, length: u32| -> Vec<Raw> {
+            (0..length)
+                .map(|i| Raw::from(&self.load_slot(snapshot, &bucket_addr(name, part, i))[..]))
+                .collect()
+        };
+        let hashes = load_part(HASH_KEY_PART, hash_length);
+        let items = load_part(ITEM_PART, item_length);
+        let filter = load_part(BLOOM_FILTER_PART, filter_length)
+            .iter()
+            .flat_map(|r| r.bytes)
+            .collect();
+        (hashes, items, BloomFilter::from_bytes(filter))
+    }
+
+    /// Upgrade the storage saved by the previous version of sewup, and returns the previous
+    /// version if the storage is upgraded.
+    /// This can be called in a `#[ewasm_fn]` to migrate the storage explicitly.
+    pub fn migrate() -> Result<Option<u8>> {
+        match Self::new()?.load_header(&Snapshot::latest()) {
+            Some(config) if VERSION != config[1] => {
+                Self::load(None)?;
+                Ok(Some(config[1]))
+            }
+            _ => Ok(None),
         }
     }
 
@@ -191,50 +349,70 @@ impl Store {
         self.tenants.insert(name, Some(raw_bucket));
     }
 
-    /// Save to storage
+    /// Save to storage, only the slots changed since loaded are written,
+    /// and returns the size of bucket directory
+    /// If the store is loaded from a past block, the store is rolled back to the block.
     pub fn commit(&self) -> Result<u32> {
         for (k, v) in self.tenants.iter() {
             if v.is_none() {
                 return Err(Error::BucketNotSync(k.to_string()).into());
             }
         }
+        let mut slots: BTreeMap<[u8; 32], [u8; 32]> = BTreeMap::new();
+
+        let mut directory: Vec<(&String, BucketSize)> =
+            self.directory.iter().map(|(n, s)| (n, *s)).collect();
+        for name in self.tenants.keys() {
+            let (hashes, items, filter) = self.tenants[name]
+                .as_ref()
+                .expect("bucket is checked synced");
+            let filter: Vec<Raw> = filter.as_bytes().chunks(32).map(Raw::from).collect();
+            for (part, raws) in [
+                (HASH_KEY_PART, hashes),
+                (ITEM_PART, items),
+                (BLOOM_FILTER_PART, &filter),
+            ] {
+                for (i, raw) in raws.iter().enumerate() {
+                    slots.insert(bucket_addr(name, part, i as u32), raw.to_bytes32());
+                }
+            }
+            directory.push((
+                name,
+                (hashes.len() as u32, items.len() as u32, filter.len() as u32),
+            ));
+        }
+        directory.sort();
+
+        let bin = bincode::serialize(&directory).expect("serialize bucket directory fail");
+        let length = bin.len();
+
         let mut buffer = [0u8; 32];
         KV_FEATURE.to_be_bytes().swap_with_slice(&mut buffer[0..1]);
         VERSION.to_be_bytes().swap_with_slice(&mut buffer[1..2]);
         self._features
             .to_le_bytes()
             .swap_with_slice(&mut buffer[2..4]);
-
-        // TODO: store as really need
-        let bin = bincode::serialize(&self.tenants).expect("serialize db binary fail");
-        let length = bin.len();
-
-        let mut len_buffer = bin.len().to_be_bytes();
+        let mut len_buffer = (length as u32).to_be_bytes();
         len_buffer.swap_with_slice(&mut buffer[28..32]);
+        let namespace = Namespace::new(KV_NAMESPACE);
+        slots.insert(namespace.index_addr(0), buffer);
 
-        storage_store(&CONFIG_ADDR.into(), &buffer.into());
-
-        let mut addr: [u8; 32] = [0; 32];
-        let mut storage_index = 0;
-        let mut iter = bin.chunks_exact(32);
-        while storage_index * 32 < length as usize {
-            storage_index += 1;
-            storage_index_to_addr(storage_index, &mut addr);
+        for (i, chunk) in bin.chunks(32).enumerate() {
+            slots.insert(namespace.index_addr(i + 1), Raw::from(chunk).to_bytes32());
+        }
 
-            if let Some(chunk) = iter.next() {
-                let part: [u8; 32] = chunk.try_into().unwrap();
-                storage_store(&addr.into(), &part.into());
-            } else {
-                let remainder = iter.remainder();
-                storage_index_to_addr(storage_index, &mut addr);
-                let mut part = [0u8; 32];
-                for i in 0..length & 31 {
-                    part[i] = remainder[i];
-                }
-                storage_store(&addr.into(), &part.into());
-                break;
+        let mut change_log = ChangeLog::new(self._features & Feature::Versioning as u16 > 0);
+        for (addr, value) in slots.iter() {
+            if self.loaded.get(addr) != Some(value) {
+                change_log.store(addr, value);
+            }
+        }
+        for (addr, value) in self.loaded.iter() {
+            if !slots.contains_key(addr) && *value != [0u8; 32] {
+                change_log.store(addr, &[0u8; 32]);
             }
         }
+        change_log.commit();
         Ok(length as u32)
     }
 }
@@ -1,13 +1,15 @@
 use std::marker::PhantomData;
 
+use crate::kv::traits::{to_ordered_bytes, Key, Value};
 use crate::kv::*;
+use crate::{Deserialize, Serialize};
 
 #[cfg(feature = "default")]
 #[test]
 fn test_pop_front_for_bucket() {
     let mut bucket = Bucket {
         name: "test_bucket".into(),
-        raw_bucket: (vec![], vec![]),
+        raw_bucket: (vec![], vec![], Default::default()),
         phantom_k: PhantomData::<usize>,
         phantom_v: PhantomData::<usize>,
     };
@@ -25,7 +27,7 @@ fn test_pop_front_for_bucket() {
 fn test_pop_for_bucket() {
     let mut bucket = Bucket {
         name: "test_bucket".into(),
-        raw_bucket: (vec![], vec![]),
+        raw_bucket: (vec![], vec![], Default::default()),
         phantom_k: PhantomData::<usize>,
         phantom_v: PhantomData::<usize>,
     };
@@ -44,7 +46,7 @@ fn test_pop_for_bucket() {
 fn test_pop_back_for_bucket() {
     let mut bucket = Bucket {
         name: "test_bucket".into(),
-        raw_bucket: (vec![], vec![]),
+        raw_bucket: (vec![], vec![], Default::default()),
         phantom_k: PhantomData::<usize>,
         phantom_v: PhantomData::<usize>,
     };
@@ -62,7 +64,7 @@ fn test_pop_back_for_bucket() {
 fn test_next_key_of_bucket() {
     let mut bucket = Bucket {
         name: "test_bucket".into(),
-        raw_bucket: (vec![], vec![]),
+        raw_bucket: (vec![], vec![], Default::default()),
         phantom_k: PhantomData::<usize>,
         phantom_v: PhantomData::<usize>,
     };
@@ -76,7 +78,7 @@ fn test_next_key_of_bucket() {
 fn test_prev_key_of_bucket() {
     let mut bucket = Bucket {
         name: "test_bucket".into(),
-        raw_bucket: (vec![], vec![]),
+        raw_bucket: (vec![], vec![], Default::default()),
         phantom_k: PhantomData::<usize>,
         phantom_v: PhantomData::<usize>,
     };
@@ -84,3 +86,277 @@ fn test_prev_key_of_bucket() {
     bucket.set(2, 2);
     assert_eq!(bucket.prev_key(2), Some((1, 1)));
 }
+
+#[cfg(feature = "default")]
+#[test]
+fn test_bloom_filter_without_false_negative() {
+    let mut filter = BloomFilter::with_capacity(100);
+    let mut hashes = Vec::new();
+    for i in 0..100usize {
+        let hash = i.gen_hash().unwrap();
+        filter.insert(&hash);
+        hashes.push(hash);
+    }
+    for hash in hashes.iter() {
+        assert!(filter.may_contain(hash));
+    }
+
+    let rebuilt = BloomFilter::from_hashes(hashes.iter().map(|h| &h[..]), hashes.len());
+    assert!(rebuilt.capacity() >= 200);
+    for hash in hashes.iter() {
+        assert!(rebuilt.may_contain(hash));
+    }
+
+    assert!(!BloomFilter::default().may_contain(&hashes[0]));
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_bloom_filter_of_bucket() {
+    let mut bucket = Bucket {
+        name: "test_bucket".into(),
+        raw_bucket: (vec![], vec![], Default::default()),
+        phantom_k: PhantomData::<usize>,
+        phantom_v: PhantomData::<usize>,
+    };
+    for i in 0..50 {
+        bucket.set(i, i).unwrap();
+    }
+    for i in 0..50 {
+        assert!(bucket.contains(i).unwrap());
+        assert_eq!(bucket.get(i).unwrap(), Some(i));
+    }
+    assert!(bucket.raw_bucket.2.capacity() >= 50);
+
+    bucket.remove(3).unwrap();
+    assert!(!bucket.contains(3).unwrap());
+    for i in (0..50).filter(|i| *i != 3) {
+        assert!(bucket.contains(i).unwrap());
+    }
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_ordered_bytes_of_keys() {
+    assert!(to_ordered_bytes(&-1i32).unwrap() < to_ordered_bytes(&0i32).unwrap());
+    assert!(to_ordered_bytes(&-2.5f64).unwrap() < to_ordered_bytes(&-1.0f64).unwrap());
+    assert!(to_ordered_bytes(&255u32).unwrap() < to_ordered_bytes(&256u32).unwrap());
+    assert!(to_ordered_bytes(&("a", 9u8)).unwrap() < to_ordered_bytes(&("ab", 0u8)).unwrap());
+    assert!(to_ordered_bytes(&(1u8, "b")).unwrap() < to_ordered_bytes(&(2u8, "a")).unwrap());
+    assert!(to_ordered_bytes(&None::<u8>).unwrap() < to_ordered_bytes(&Some(0u8)).unwrap());
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_ordered_iteration_for_bucket() {
+    let mut bucket = Bucket {
+        name: "test_bucket".into(),
+        raw_bucket: (vec![], vec![], Default::default()),
+        phantom_k: PhantomData::<usize>,
+        phantom_v: PhantomData::<usize>,
+    };
+    for i in [300, 2, 1000, 1, 256, 3].iter() {
+        bucket.set(*i, i + 1).unwrap();
+    }
+
+    let keys: Vec<usize> = bucket.iter_ordered().map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![1, 2, 3, 256, 300, 1000]);
+
+    let items: Vec<(usize, usize)> = bucket.range(3..=300).collect();
+    assert_eq!(items, vec![(3, 4), (256, 257), (300, 301)]);
+
+    let keys: Vec<usize> = bucket.range(..256).rev().map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![3, 2, 1]);
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_prefix_iteration_for_bucket() {
+    let mut bucket = Bucket {
+        name: "test_bucket".into(),
+        raw_bucket: (vec![], vec![], Default::default()),
+        phantom_k: PhantomData::<String>,
+        phantom_v: PhantomData::<usize>,
+    };
+    for (i, k) in ["user:2", "item:1", "user:10", "user:1", "users"]
+        .iter()
+        .enumerate()
+    {
+        bucket.set(k.to_string(), i).unwrap();
+    }
+
+    let keys: Vec<String> = bucket
+        .iter_prefix("user:".to_string())
+        .map(|(k, _)| k.trim_end_matches('\u{0}').to_string())
+        .collect();
+    assert_eq!(keys, vec!["user:1", "user:10", "user:2"]);
+
+    let keys: Vec<String> = bucket
+        .iter_prefix("user".to_string())
+        .rev()
+        .map(|(k, _)| k.trim_end_matches('\u{0}').to_string())
+        .collect();
+    assert_eq!(keys, vec!["users", "user:2", "user:10", "user:1"]);
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_migrate_buckets_from_version_0() {
+    use std::collections::HashMap;
+
+    use crate::kv::migration::MIGRATIONS;
+    use crate::migration::upgrade;
+    use crate::types::Raw;
+
+    let mut bucket = Bucket {
+        name: "test_bucket".into(),
+        raw_bucket: (vec![], vec![], Default::default()),
+        phantom_k: PhantomData::<usize>,
+        phantom_v: PhantomData::<usize>,
+    };
+    for i in 0..10 {
+        bucket.set(i, i * 2).unwrap();
+    }
+    let (hashes, items, _) = bucket.raw_bucket;
+    let mut tenants_v0: HashMap<String, Option<(Vec<Raw>, Vec<Raw>)>> = HashMap::new();
+    tenants_v0.insert("test_bucket".into(), Some((hashes, items)));
+    let bin = bincode::serialize(&tenants_v0).unwrap();
+
+    let bin = upgrade(MIGRATIONS, 0, 1, bin).unwrap();
+    let mut tenants: HashMap<String, Option<RawBucket>> = bincode::deserialize(&bin).unwrap();
+    let bucket = Bucket::<usize, usize> {
+        name: "test_bucket".into(),
+        raw_bucket: tenants.remove("test_bucket").unwrap().unwrap(),
+        phantom_k: PhantomData,
+        phantom_v: PhantomData,
+    };
+    for i in 0..10 {
+        assert_eq!(bucket.get(i).unwrap(), Some(i * 2));
+    }
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_store_commit_and_load_in_memory() {
+    use crate::storage::{with_backend, MemoryStorage};
+    use crate::types::Address;
+
+    with_backend(|memory| *memory = MemoryStorage::default());
+
+    let mut store = Store::new().unwrap();
+    let mut bucket = store.bucket::<usize, usize>("numbers").unwrap();
+    for i in 0..10 {
+        bucket.set(i, i * 2).unwrap();
+    }
+    store.save(bucket);
+    let mut owners = store.bucket::<Address, Address>("owners").unwrap();
+    let addr = Address { inner: [7u8; 20] };
+    owners.set(addr.clone(), addr.clone()).unwrap();
+    store.save(owners);
+    store.commit().unwrap();
+
+    let mut store = Store::load(None).unwrap();
+    let mut buckets = store.buckets();
+    buckets.sort();
+    assert_eq!(buckets, vec!["numbers", "owners"]);
+    let bucket = store.bucket::<usize, usize>("numbers").unwrap();
+    for i in 0..10 {
+        assert_eq!(bucket.get(i).unwrap(), Some(i * 2));
+    }
+    let owners = store.bucket::<Address, Address>("owners").unwrap();
+    assert_eq!(owners.get(addr.clone()).unwrap(), Some(addr));
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_store_versioning_in_memory() {
+    use crate::storage::{with_backend, MemoryStorage};
+
+    with_backend(|memory| {
+        *memory = MemoryStorage::default();
+        memory.block_number = 1;
+    });
+
+    let mut store = Store::new().unwrap();
+    store.enable_feature(Feature::Versioning);
+    let mut bucket = store.bucket::<usize, usize>("numbers").unwrap();
+    bucket.set(1, 10).unwrap();
+    store.save(bucket);
+    store.commit().unwrap();
+
+    with_backend(|memory| memory.block_number = 5);
+    let mut store = Store::load(None).unwrap();
+    let mut bucket = store.bucket::<usize, usize>("numbers").unwrap();
+    bucket.set(1, 50).unwrap();
+    store.save(bucket);
+    store.commit().unwrap();
+
+    assert_eq!(Store::versions().unwrap(), vec![1, 5]);
+
+    let mut old_store = Store::load(Some(3)).unwrap();
+    let bucket = old_store.bucket::<usize, usize>("numbers").unwrap();
+    assert_eq!(bucket.get(1).unwrap(), Some(10));
+
+    let mut store = Store::load(None).unwrap();
+    let bucket = store.bucket::<usize, usize>("numbers").unwrap();
+    assert_eq!(bucket.get(1).unwrap(), Some(50));
+
+    // The store before the first commit is rebuilt from the journal
+    let old_store = Store::load(Some(0)).unwrap();
+    assert!(old_store.buckets().is_empty());
+    assert!(Store::load(Some(-1)).is_err());
+}
+
+#[cfg(feature = "default")]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Document {
+    id: u32,
+    content: Vec<u8>,
+}
+
+#[cfg(feature = "default")]
+impl Key for Document {}
+
+#[cfg(feature = "default")]
+impl Value for Document {}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_large_key_and_value_for_bucket() {
+    let mut bucket = Bucket {
+        name: "test_bucket".into(),
+        raw_bucket: (vec![], vec![], Default::default()),
+        phantom_k: PhantomData::<Document>,
+        phantom_v: PhantomData::<Document>,
+    };
+    let large = Document {
+        id: 1,
+        content: vec![7u8; 300],
+    };
+    let small = Document {
+        id: 2,
+        content: vec![],
+    };
+    bucket.set(large.clone(), large.clone()).unwrap();
+    bucket.set(small.clone(), small.clone()).unwrap();
+    assert_eq!(bucket.get(large.clone()).unwrap(), Some(large));
+    assert_eq!(bucket.get(small.clone()).unwrap(), Some(small));
+}
+
+#[cfg(feature = "default")]
+#[test]
+fn test_decode_with_legacy_header() {
+    use crate::types::Row;
+
+    let document = Document {
+        id: 3,
+        content: b"legacy".to_vec(),
+    };
+    let bin = bincode::serialize(&document).unwrap();
+    let mut buffer = vec![((bin.len() + 1) & 31) as u8];
+    buffer.extend_from_slice(&bin);
+    let mut row = Row::from(buffer);
+    row.make_buffer();
+    assert_eq!(Document::from_row_value(&row).unwrap(), document);
+    assert_eq!(Document::from_row_key(&row).unwrap(), document);
+}
@@ -8,31 +8,32 @@ use cryptoxide::mac::Mac;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::header;
+use crate::ordered::{escape_bytes, to_ordered_bytes};
 use crate::types::{Address, Raw, Row};
 
 /// helps to serialize struct as Key to row or deserialized from row
 /// ```compile_fail
-/// | 1st bytes | ...    | padding                   |
-/// |-----------|--------|---------------------------|
-/// | Header    | Binary | padding to n times Byte32 |
+/// | 1st ~ 5th bytes | ...    | padding                   |
+/// |-----------------|--------|---------------------------|
+/// | Header          | Binary | padding to n times Byte32 |
 /// ```
-/// Header is the number of bytes for binary
+/// Header is the version and the number of bytes for binary, please check out
+/// [`header`](crate::header) for the detail.
+///
+/// The order of keys is decided by `to_ordered_bytes`, which is an order-preserving encoding,
+/// please check out [`to_ordered_bytes`](crate::ordered::to_ordered_bytes) for the detail.
 pub trait Key: Clone + Sized + Serialize + DeserializeOwned {
     fn from_row_key(r: &Row) -> Result<Self> {
         let buffer: &[u8] = r.borrow();
-        let header = buffer[0] as usize;
-        let instance: Self = bincode::deserialize(&buffer[1..buffer.len() - header])
-            .expect("load binary to key fail");
+        let instance: Self =
+            bincode::deserialize(header::decode(buffer)?).expect("load binary to key fail");
         Ok(instance)
     }
 
     fn to_row_key(&self) -> Result<Row> {
-        let mut bin = bincode::serialize(&self).expect("serialize a key fail");
-        let length = bin.len();
-        let header = ((length + 1) & 31) as u8; // padding bytes
-        let mut vec = vec![header];
-        vec.append(&mut bin);
-        Ok(vec.into())
+        let bin = bincode::serialize(&self).expect("serialize a key fail");
+        Ok(header::encode(&bin).into())
     }
 
     fn gen_hash_key(&self, key_len: u32, value_len: u32) -> Result<Raw> {
@@ -51,6 +52,12 @@ pub trait Key: Clone + Sized + Serialize + DeserializeOwned {
         Ok(Raw::from(&bytes))
     }
 
+    /// The order-preserving encoding of the key, the items in bucket are sorted with these bytes
+    /// when iterating in order
+    fn to_ordered_bytes(&self) -> Result<Vec<u8>> {
+        Ok(to_ordered_bytes(self)?)
+    }
+
     fn gen_hash(&self) -> Result<[u8; 24]> {
         let mut hash: [u8; 24] = [0; 24];
         let bin = bincode::serialize(&self).expect("serialize a key fail");
@@ -95,13 +102,19 @@ impl Key for Row {
     fn from_row_key(x: &Row) -> Result<Self> {
         Ok(x.clone())
     }
+    fn to_ordered_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.inner.iter().flat_map(|r| r.bytes).collect())
+    }
 }
 
 impl Key for Address {
     fn from_row_key(_x: &Row) -> Result<Self> {
         #[cfg(not(target_arch = "wasm32"))]
-        let addr = Address {
-            ..Default::default()
+        let addr = {
+            let raw: Raw = _x.try_into().expect("row key should at least one raw");
+            Address {
+                inner: raw.to_bytes20(),
+            }
         };
         #[cfg(target_arch = "wasm32")]
         let addr: Address = {
@@ -136,7 +149,7 @@ impl Key for Address {
     }
     fn to_row_key(&self) -> Result<Row> {
         #[cfg(not(target_arch = "wasm32"))]
-        let raw: Raw = Raw::default();
+        let raw: Raw = Raw::from_raw_address(&self.inner);
 
         #[cfg(target_arch = "wasm32")]
         let raw: Raw = self.into();
@@ -171,6 +184,11 @@ impl Key for String {
     fn to_row_key(&self) -> Result<Row> {
         Ok(self.into())
     }
+    /// The padding of `Row` is trimmed, such that the string loaded from bucket is in the same
+    /// order of the string it set
+    fn to_ordered_bytes(&self) -> Result<Vec<u8>> {
+        Ok(escape_bytes(self.trim_end_matches('\u{0}').as_bytes()))
+    }
 }
 
 macro_rules! sized_string_key {
@@ -7,3 +7,6 @@ pub use value::Value;
 
 pub mod vec;
 pub use vec::VecLike;
+
+pub use crate::ordered;
+pub use crate::ordered::to_ordered_bytes;
@@ -1,37 +1,32 @@
 //! helps serialized value object into raw and also deserialize the raw back to object
 use std::borrow::Borrow;
 use std::convert::TryFrom;
-#[cfg(target_arch = "wasm32")]
 use std::convert::TryInto;
 
 use anyhow::Result;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::header;
 use crate::types::{Address, Raw, Row};
 
 /// helps to serialize struct as Value to row or deserialized from row
 /// ```compile_fail
-/// | 1st bytes | ...    | padding                   |
-/// |-----------|--------|---------------------------|
-/// | Header    | Binary | padding to n times Byte32 |
+/// | 1st ~ 5th bytes | ...    | padding                   |
+/// |-----------------|--------|---------------------------|
+/// | Header          | Binary | padding to n times Byte32 |
 /// ```
-/// Header is the number of bytes for binary
-//TODO make Header bigger for big value object storage
+/// Header is the version and the number of bytes for binary, please check out
+/// [`header`](crate::header) for the detail.
 pub trait Value: Sized + Serialize + DeserializeOwned {
     fn to_row_value(&self) -> Result<Row> {
-        let mut bin = bincode::serialize(&self).expect("serialize a value fail");
-        let length = bin.len();
-        let header = ((length + 1) & 31) as u8; // padding bytes
-        let mut vec = vec![header];
-        vec.append(&mut bin);
-        Ok(vec.into())
+        let bin = bincode::serialize(&self).expect("serialize a value fail");
+        Ok(header::encode(&bin).into())
     }
     fn from_row_value(r: &Row) -> Result<Self> {
         let buffer: &[u8] = r.borrow();
-        let header = buffer[0] as usize;
-        let instance: Self = bincode::deserialize(&buffer[1..buffer.len() - header])
-            .expect("load binary to row fail fail");
+        let instance: Self =
+            bincode::deserialize(header::decode(buffer)?).expect("load binary to row fail fail");
         Ok(instance)
     }
 }
@@ -59,8 +54,11 @@ impl Value for Row {
 impl Value for Address {
     fn from_row_value(_x: &Row) -> Result<Self> {
         #[cfg(not(target_arch = "wasm32"))]
-        let addr = Address {
-            ..Default::default()
+        let addr = {
+            let raw: Raw = _x.try_into().expect("row key should at least one raw");
+            Address {
+                inner: raw.to_bytes20(),
+            }
         };
         #[cfg(target_arch = "wasm32")]
         let addr: Address = {
@@ -95,7 +93,7 @@ impl Value for Address {
     }
     fn to_row_value(&self) -> Result<Row> {
         #[cfg(not(target_arch = "wasm32"))]
-        let raw: Raw = Raw::default();
+        let raw: Raw = Raw::from_raw_address(&self.inner);
 
         #[cfg(target_arch = "wasm32")]
         let raw: Raw = self.into();
@@ -35,11 +35,29 @@ pub use rdb::*;
 
 pub mod errors;
 
+/// help you encode the length of binary saved in rows
+#[cfg(any(feature = "kv", feature = "rdb"))]
+pub mod header;
+
+/// help you load the storage as of an earlier block
+#[cfg(any(feature = "kv", feature = "rdb"))]
+pub mod journal;
+
+#[cfg(any(feature = "kv", feature = "rdb"))]
+pub(crate) mod migration;
+
+/// help you encode the keys of bucket and the indexed fields of table in order
+#[cfg(any(feature = "kv", feature = "rdb"))]
+pub mod ordered;
+
 pub mod primitives;
 
 #[allow(dead_code)]
 pub mod utils;
 
+/// help you allocate the slots of storage in different namespaces
+pub mod storage;
+
 /// The run time helps user to setup the contract testing environment
 #[cfg(not(target_arch = "wasm32"))]
 pub mod runtimes;
@@ -0,0 +1,70 @@
+//! The storage format of kv and rdb is versioned by the 1st byte of the header, each previous
+//! version registers an upgrade step to the next version, such that the storage saved by the
+//! previous version of sewup can be upgraded step by step when loading.
+use anyhow::Result;
+
+use crate::errors::StorageError;
+
+/// An upgrade step of the storage format from version `from` to version `from + 1`
+pub(crate) struct Migration<T> {
+    pub from: u8,
+    pub upgrade: fn(T) -> Result<T>,
+}
+
+/// Upgrade the data from the `version` to the `target` version with the registered steps
+pub(crate) fn upgrade<T>(steps: &[Migration<T>], version: u8, target: u8, data: T) -> Result<T> {
+    if version > target {
+        return Err(StorageError::VersionNotSupported(version).into());
+    }
+    let mut data = data;
+    for v in version..target {
+        let step = steps
+            .iter()
+            .find(|s| s.from == v)
+            .ok_or(StorageError::MigrationNotRegistered(v))?;
+        data = (step.upgrade)(data)?;
+    }
+    Ok(data)
+}
+
+#[cfg(feature = "default")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_step_by_step() {
+        let steps: &[Migration<Vec<u8>>] = &[
+            Migration {
+                from: 1,
+                upgrade: |mut v| {
+                    v.push(2);
+                    Ok(v)
+                },
+            },
+            Migration {
+                from: 0,
+                upgrade: |mut v| {
+                    v.push(1);
+                    Ok(v)
+                },
+            },
+        ];
+        assert_eq!(upgrade(steps, 0, 2, vec![0]).unwrap(), vec![0, 1, 2]);
+        assert_eq!(upgrade(steps, 1, 2, vec![1]).unwrap(), vec![1, 2]);
+        assert_eq!(
+            upgrade(steps, 0, 3, vec![0])
+                .unwrap_err()
+                .downcast::<StorageError>()
+                .unwrap(),
+            StorageError::MigrationNotRegistered(2)
+        );
+        assert_eq!(
+            upgrade(steps, 3, 2, vec![0])
+                .unwrap_err()
+                .downcast::<StorageError>()
+                .unwrap(),
+            StorageError::VersionNotSupported(3)
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! Order-preserving encoding for keys
+//!
+//! The bytes of encoded keys can be compared directly, and the order of bytes is the same as the
+//! order of keys, such that the items in a bucket can be iterated in the order of keys, and the
+//! records of a table can be looked up in the order of indexed fields.
+//! - unsigned integers are encoded in big endian
+//! - signed integers are encoded in big endian with the sign bit flipped
+//! - floats are encoded as the bits with the sign bit flipped, and all the bits flipped for
+//!   negative numbers, `-0.0` is encoded as `0.0` and all NaNs are encoded the same
+//! - strings and bytes are escaped (`0x00` as `0x00 0xFF`) and terminated with `0x00 0x00`
+//! - `None` is `0x00`, and `Some(T)` is `0x01` followed by `T`
+//! - each element of sequence is leaded by `0x01`, and the sequence is terminated by `0x00`
+//! - the entries of map are encoded as the sequence of key and value pairs sorted by the encoded
+//!   keys, such that the maps with the same entries are encoded the same in any iteration order
+//! - the fields of structures and tuples are concatenated in order
+//! - the variant of enum is encoded as the index in big endian
+use serde::{ser, Serialize};
+
+use crate::errors::EncodingError as Error;
+
+/// Encode the instance with the order-preserving encoding
+pub fn to_ordered_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut serializer = OrderedSerializer {
+        output: Vec::new(),
+        maps: Vec::new(),
+    };
+    value.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+/// Escape the bytes without the terminator, which is used for the string at the top level of
+/// key, such that the encoding of a string is the prefix of the encoding of longer strings.
+pub fn escape_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(bytes.len());
+    for b in bytes.iter() {
+        output.push(*b);
+        if *b == 0 {
+            output.push(0xFF);
+        }
+    }
+    output
+}
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::OrderedEncodingFail(msg.to_string())
+    }
+}
+
+struct OrderedSerializer {
+    output: Vec<u8>,
+    /// The encoded entries of the maps being serialized, which are sorted when the map ends
+    maps: Vec<Vec<(Vec<u8>, Vec<u8>)>>,
+}
+
+impl OrderedSerializer {
+    fn write_terminated(&mut self, bytes: &[u8]) {
+        self.output.append(&mut escape_bytes(bytes));
+        self.output.extend_from_slice(&[0, 0]);
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut OrderedSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.output.push(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.serialize_u8((v as u8) ^ 0x80)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.serialize_u16((v as u16) ^ 0x8000)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.serialize_u32((v as u32) ^ 0x8000_0000)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.serialize_u64((v as u64) ^ 0x8000_0000_0000_0000)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.output.push(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.output.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.output.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.output.extend_from_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        let bits = if v == 0.0 {
+            0
+        } else if v.is_nan() {
+            f32::NAN.to_bits()
+        } else {
+            v.to_bits()
+        };
+        let bits = if bits & 0x8000_0000 > 0 {
+            !bits
+        } else {
+            bits ^ 0x8000_0000
+        };
+        self.serialize_u32(bits)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        let bits = if v == 0.0 {
+            0
+        } else if v.is_nan() {
+            f64::NAN.to_bits()
+        } else {
+            v.to_bits()
+        };
+        let bits = if bits & 0x8000_0000_0000_0000 > 0 {
+            !bits
+        } else {
+            bits ^ 0x8000_0000_0000_0000
+        };
+        self.serialize_u64(bits)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.serialize_u32(v as u32)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.write_terminated(v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.write_terminated(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.output.push(0);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        self.output.push(1);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.output.extend_from_slice(&variant_index.to_be_bytes());
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        self.output.extend_from_slice(&variant_index.to_be_bytes());
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        self.maps.push(Vec::new());
+        Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        self.output.extend_from_slice(&variant_index.to_be_bytes());
+        Ok(self)
+    }
+}
+
+impl<'a> ser::SerializeSeq for &'a mut OrderedSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.output.push(1);
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.output.push(0);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for &'a mut OrderedSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        let key = to_ordered_bytes(key)?;
+        if let Some(entries) = self.maps.last_mut() {
+            entries.push((key, Vec::new()));
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let value = to_ordered_bytes(value)?;
+        if let Some((_, v)) = self.maps.last_mut().and_then(|entries| entries.last_mut()) {
+            *v = value;
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        let mut entries = self.maps.pop().unwrap_or_default();
+        entries.sort();
+        for (mut key, mut value) in entries.into_iter() {
+            self.output.push(1);
+            self.output.append(&mut key);
+            self.output.append(&mut value);
+        }
+        self.output.push(0);
+        Ok(())
+    }
+}
+
+macro_rules! concatenated_serializer {
+    ( $( ($t:path, $f:ident) ),* ) => {
+        $(
+            impl<'a> $t for &'a mut OrderedSerializer {
+                type Ok = ();
+                type Error = Error;
+
+                fn $f<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+                    value.serialize(&mut **self)
+                }
+
+                fn end(self) -> Result<(), Error> {
+                    Ok(())
+                }
+            }
+        )*
+    }
+}
+
+concatenated_serializer!(
+    (ser::SerializeTuple, serialize_element),
+    (ser::SerializeTupleStruct, serialize_field),
+    (ser::SerializeTupleVariant, serialize_field)
+);
+
+impl<'a> ser::SerializeStruct for &'a mut OrderedSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for &'a mut OrderedSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
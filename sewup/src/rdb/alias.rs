@@ -0,0 +1,68 @@
+//! The aliases of tables, which associate the signature of the new name of a renamed table with
+//! the signature of the table saved in `TableInfo`, such that the indexes, the overflow heap and
+//! the relations keyed by the saved signature are not moved when the table is renamed.
+//!
+//! The aliases are saved in the slots addressed by hash, and loaded with the db.
+//! ```compile_fail
+//! | address            | 0th ~ 27th | 28th ~ 31st            |
+//! |--------------------|------------|------------------------|
+//! | sha3("alias", 0)   | -          | size of binary (BE)    |
+//! | sha3("alias", i)   | the binary of aliases               |
+//! ```
+use std::convert::TryInto;
+
+use crate::journal::{ChangeLog, Snapshot};
+use crate::rdb::db::TableSig;
+use crate::utils::sha3_256;
+
+fn alias_addr(idx: u32) -> [u8; 32] {
+    let mut input: Vec<u8> = "alias".as_bytes().into();
+    input.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&input)
+}
+
+/// The number of slots of the binary, the empty aliases are not saved
+fn slot_length(aliases: &[(TableSig, TableSig)]) -> u32 {
+    if aliases.is_empty() {
+        0
+    } else {
+        (bincode::serialized_size(aliases).expect("aliases size fail") as u32 + 31) / 32
+    }
+}
+
+/// Load the aliases, which are the signatures of names and the signatures of saved tables
+pub(crate) fn load_aliases(snapshot: &Snapshot) -> Vec<(TableSig, TableSig)> {
+    let header = snapshot.load(&alias_addr(0));
+    let size = u32::from_be_bytes(header[28..32].try_into().expect("load alias size fail"));
+    if size == 0 {
+        return Vec::new();
+    }
+    let mut bin: Vec<u8> = Vec::with_capacity(size as usize + 31);
+    for i in 1..=(size + 31) / 32 {
+        bin.extend_from_slice(&snapshot.load(&alias_addr(i)));
+    }
+    bincode::deserialize(&bin[0..size as usize]).expect("load aliases fail")
+}
+
+/// Save the aliases, the slots of the previous aliases no longer used are cleared
+pub(crate) fn store_aliases(
+    aliases: &[(TableSig, TableSig)],
+    previous: &[(TableSig, TableSig)],
+    change_log: &mut ChangeLog,
+) {
+    let mut header = [0u8; 32];
+    if !aliases.is_empty() {
+        let bin = bincode::serialize(aliases).expect("serialize aliases fail");
+        header[28..32].copy_from_slice(&(bin.len() as u32).to_be_bytes());
+        for (i, chunk) in bin.chunks(32).enumerate() {
+            let mut buffer = [0u8; 32];
+            buffer[0..chunk.len()].copy_from_slice(chunk);
+            change_log.store(&alias_addr(i as u32 + 1), &buffer);
+        }
+    }
+    change_log.store(&alias_addr(0), &header);
+
+    for i in slot_length(aliases)..slot_length(previous) {
+        change_log.store(&alias_addr(i + 1), &[0u8; 32]);
+    }
+}
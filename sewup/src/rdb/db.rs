@@ -1,26 +1,31 @@
-#[cfg(target_arch = "wasm32")]
+use std::collections::BTreeSet;
 use std::convert::TryInto;
 use std::marker::PhantomData;
 use std::ops::Range;
 
+use crate::errors::StorageError;
+use crate::journal;
+use crate::journal::{ChangeLog, Snapshot};
+use crate::migration::upgrade;
+use crate::rdb::alias::{load_aliases, store_aliases};
 use crate::rdb::errors::Error;
+use crate::rdb::heap::clear_heap;
+use crate::rdb::index::{clear_index, update_entry};
+use crate::rdb::migration::MIGRATIONS;
+use crate::rdb::relation::{clear_relations, store_detached, unregister_relations};
+use crate::rdb::schema::{load_schema_version, store_schema_version};
+use crate::rdb::slots::{clear_slot_map, SlotMap};
 use crate::rdb::table::Table;
-use crate::rdb::traits::{Record, HEADER_SIZE};
+use crate::rdb::traits::{Record, HEADER_SIZE, OVERFLOW_HEADER};
 use crate::rdb::Feature;
-#[cfg(target_arch = "wasm32")]
-use crate::utils::storage_index_to_addr;
+use crate::storage::{Namespace, RDB_NAMESPACE};
 use crate::{Deserialize, Serialize, SerializeTrait};
 
 use anyhow::Result;
-#[cfg(target_arch = "wasm32")]
-use ewasm_api::{storage_load, storage_store};
 use tiny_keccak::{Hasher, Keccak};
 
-#[cfg(target_arch = "wasm32")]
 const RDB_FEATURE: u8 = 1;
-const VERSION: u8 = 0;
-#[cfg(target_arch = "wasm32")]
-const CONFIG_ADDR: [u8; 32] = [0; 32];
+const VERSION: u8 = 2;
 
 pub(crate) type TableSig = [u8; 4];
 
@@ -33,19 +38,39 @@ pub struct TableInfo {
     pub record_raw_size: u32,
 }
 
+/// The table dropped from the db, whose rows and slots addressed by the signature are cleared
+/// when the db is committed
+struct DroppedTable {
+    info: TableInfo,
+    /// The indexed fields of the table
+    indexes: Vec<&'static str>,
+    /// The parent tables the relations of the table are registered to
+    parents: Vec<TableSig>,
+}
+
 /// DB is a storage space for an account in a specific block.
 /// We can import the storage from a past block, and we only commit the storage
 /// into the latest block.
+/// The past blocks can be imported only if the `Versioning` feature is enabled before the
+/// commits of the blocks, and `Db::versions()` lists the blocks can be imported.
 ///
 /// ## Storage map
 /// ```compile_fail
-/// | 0th ~ 31th bytes | dynamic size | dynamic size              | dynamic size |
-/// |------------------|--------------|---------------------------|--------------|
-/// | DB header        | Table info   | Table data of first table | ...          |
+/// | 0th ~ 31th bytes | dynamic size |
+/// |------------------|--------------|
+/// | DB header        | Table info   |
 /// ```
 ///
+/// The slots are allocated in the namespace of rdb, such that the Db can be used with kv Store
+/// in the same contract.
+/// The rows of each table are saved from the 0th slot in the namespace of the table, which is
+/// named by the signature of table, so the commit of a table only writes the changed rows and
+/// never moves the other tables. `TableInfo::range` is the slots used in the namespace of table.
+/// The tables are saved after the table info in the namespace of rdb by version 1, and they are
+/// moved into the namespaces of tables when upgrading.
+///
 /// ### DB Header
-/// The fist 32 bytes are reserved as header of the store,
+/// The 0th slot in the namespace of rdb is reserved as header of the store,
 ///
 /// ```compile_fail
 /// | 0th            | 1st          | 2nd ~ 3rd         | ... | 28th ~ 31st              |
@@ -60,6 +85,25 @@ pub struct Db {
     version: u8,
     _features: u16,
     pub(crate) table_info: Vec<TableInfo>,
+    #[serde(skip)]
+    pub(crate) snapshot: Snapshot,
+    /// The namespace the db loaded from, which is the root space if the db saved by previous
+    /// version is loaded from a past block
+    #[serde(skip)]
+    pub(crate) namespace: Namespace,
+    /// The tables dropped since load, which are cleared on commit
+    #[serde(skip)]
+    dropped: Vec<DroppedTable>,
+    /// The tables are saved after the table info in the namespace of db, which is the layout of
+    /// version 1 kept for the db loaded from a past block
+    #[serde(skip)]
+    pub(crate) contiguous_tables: bool,
+    /// The signatures of the names of renamed tables and the signatures saved in `TableInfo`
+    #[serde(skip)]
+    pub(crate) aliases: Vec<(TableSig, TableSig)>,
+    /// The aliases on load, the aliases are saved if they are changed
+    #[serde(skip)]
+    saved_aliases: Vec<(TableSig, TableSig)>,
 }
 
 impl Default for Db {
@@ -72,6 +116,12 @@ impl Default for Db {
             version: VERSION,
             _features,
             table_info: Vec::new(),
+            snapshot: Snapshot::latest(),
+            namespace: Namespace::new(RDB_NAMESPACE),
+            dropped: Vec::new(),
+            contiguous_tables: false,
+            aliases: Vec::new(),
+            saved_aliases: Vec::new(),
         }
     }
 }
@@ -93,63 +143,252 @@ impl Db {
         if self._features & Feature::Default as u16 > 0 {
             output.push(Feature::Default)
         }
+        if self._features & Feature::Versioning as u16 > 0 {
+            output.push(Feature::Versioning)
+        }
+        if self._features & Feature::ReuseRows as u16 > 0 {
+            output.push(Feature::ReuseRows)
+        }
         output
     }
 
+    /// Enable the feature of storage, the feature will be saved after commit
+    pub fn enable_feature(&mut self, feature: Feature) {
+        self._features |= feature as u16;
+    }
+
+    /// The block heights of the versions kept in the journal, which can be imported by `load`
+    pub fn versions() -> Result<Vec<u64>> {
+        Ok(journal::versions())
+    }
+
+    /// The change log for the commit of Db and Table
+    pub(crate) fn change_log(&self) -> ChangeLog {
+        ChangeLog::new(self._features & Feature::Versioning as u16 > 0)
+    }
+
     /// create table for storage
     pub fn create_table<T: SerializeTrait + Default + Sized + Record>(&mut self) -> Result<()> {
-        let default_instance = T::default();
-        let size = bincode::serialized_size(&default_instance)?;
-        let record_raw_size = if size == 0 {
-            0u32
-        } else {
-            (size as u32 + HEADER_SIZE) / 32 + 1
-        };
-        let info = if self.table_info.is_empty() {
-            TableInfo {
-                sig: get_table_signature(std::any::type_name::<T>()),
-                record_raw_size,
-                range: (2..2),
-            }
-        } else {
-            let TableInfo {
-                range: last_table_range,
-                ..
-            } = self.table_info[self.table_info.len() - 1].clone();
-            TableInfo {
-                sig: get_table_signature(std::any::type_name::<T>()),
-                record_raw_size,
-                range: (last_table_range.end..last_table_range.end),
-            }
-        };
-        self.table_info.push(info);
+        let sig = self
+            .table_sig(T::table_name())
+            .filter(|sig| !self.aliases.iter().any(|(_, s)| s == sig))
+            .ok_or_else(|| Error::TableSignatureUsed(T::table_name().into()))?;
+        let record_raw_size = record_raw_size::<T>()?;
+        self.table_info.push(TableInfo {
+            sig,
+            record_raw_size,
+            range: (0..0),
+        });
 
         Ok(())
     }
 
     /// get table with date loaded
+    /// The table can not be loaded if the records are saved with another schema version, please
+    /// use `migrate_table` to rewrite the records.
     pub fn table<T: SerializeTrait + Default + Sized + Record>(self) -> Result<Table<T>> {
+        self.load_table::<T>()
+    }
+
+    /// Load the table saved with the schema of `T`, and rewrite the records into the schema of
+    /// `U` by `convert`, the migrated table is saved when it is committed.
+    /// The ids of records are kept, and the indexes of `U` are rebuilt.
+    /// `T` and `U` should have the same table name, for example the old struct is kept with
+    /// `#[table(name = "person", version = 1)]` and the new struct is declared with
+    /// `#[table(name = "person", version = 2)]`.
+    pub fn migrate_table<T, U>(self, convert: impl Fn(T) -> U) -> Result<Table<U>>
+    where
+        T: SerializeTrait + Default + Sized + Record,
+        U: SerializeTrait + Default + Sized + Record,
+    {
+        if self.table_sig(T::table_name()) != self.table_sig(U::table_name()) {
+            return Err(Error::MigrationTableMismatch(
+                T::table_name().into(),
+                U::table_name().into(),
+            )
+            .into());
+        }
+        let record_raw_size = record_raw_size::<U>()?;
+        self.load_table::<T>()?.migrate(record_raw_size, convert)
+    }
+
+    fn load_table<T: SerializeTrait + Default + Sized + Record>(&self) -> Result<Table<T>> {
         let info = self
             .table_info::<T>()
-            .ok_or(Error::TableNotExist(std::any::type_name::<T>().into()))?;
+            .ok_or(Error::TableNotExist(T::table_name().into()))?;
+        let version = load_schema_version(&info.sig, &self.snapshot);
+        if version != T::SCHEMA_VERSION && info.range.start != info.range.end {
+            return Err(Error::SchemaVersionMismatch(T::table_name().into(), version).into());
+        }
         Ok(Table::<T> {
             info,
             data: Vec::new(),
             phantom: PhantomData,
+            indexes: Vec::new(),
+            heap: Default::default(),
+            changed: Default::default(),
+            deleted: Default::default(),
+            slots: Default::default(),
+            dirty_rows: Default::default(),
+            reuse_rows: false,
+            detached: false,
+            block_height: None,
         }
-        .load_data()?)
+        .load_data(self)?)
     }
 
     /// drop table
-    pub fn drop_table<T>(&mut self) {
-        let sig = get_table_signature(std::any::type_name::<T>());
-        let mut new_table_info = Vec::with_capacity(self.table_info.len() - 1);
-        for info in self.table_info.iter() {
-            if info.sig != sig {
-                new_table_info.push(info.clone());
+    /// The rows, the indexes and the other slots of the table are cleared when the db is
+    /// committed, so a table created with the same name later is empty.
+    pub fn drop_table<T: Record>(&mut self) {
+        let sig = match self.table_sig(T::table_name()) {
+            Some(sig) => sig,
+            None => return,
+        };
+        self.aliases.retain(|(_, s)| *s != sig);
+        if let Some(pos) = self.table_info.iter().position(|info| info.sig == sig) {
+            let info = self.table_info.remove(pos);
+            let parents = T::foreign_keys()
+                .iter()
+                .filter_map(|fk| self.table_sig((fk.parent)()))
+                .collect();
+            self.dropped.push(DroppedTable {
+                info,
+                indexes: T::INDEXES.iter().map(|(field, _)| *field).collect(),
+                parents,
+            });
+        }
+    }
+
+    /// Clear the rows of the dropped table and the slots addressed by the signature of table
+    fn clear_table(&self, table: &DroppedTable, change_log: &mut ChangeLog) {
+        let TableInfo {
+            sig,
+            range,
+            record_raw_size,
+        } = &table.info;
+        if *record_raw_size > 0 {
+            let row_length = (range.end - range.start) / record_raw_size;
+            for (id, row) in SlotMap::load(sig, row_length as usize, &self.snapshot).iter() {
+                let storage_idx = range.start + row as u32 * record_raw_size;
+                let buffer = self.snapshot.load(&self.table_addr(sig, storage_idx));
+                if buffer[0] == OVERFLOW_HEADER {
+                    let size = u32::from_be_bytes(
+                        buffer[1..5].try_into().expect("load overflow size fail"),
+                    );
+                    clear_heap(sig, id, size as usize, change_log);
+                }
+            }
+        }
+        for idx in range.clone() {
+            change_log.store(&self.table_addr(sig, idx), &[0u8; 32]);
+        }
+        for field in table.indexes.iter() {
+            clear_index(sig, field, &self.snapshot, change_log);
+        }
+        clear_slot_map(sig, &self.snapshot, change_log);
+        clear_relations(sig, &self.snapshot, change_log);
+        store_detached(sig, &[], &self.snapshot, change_log);
+        for parent in table.parents.iter() {
+            unregister_relations(parent, sig, &self.snapshot, change_log);
+        }
+        store_schema_version(sig, 0, &self.snapshot, change_log);
+    }
+
+    /// Delete the records of the table without the type of record, for example the child records
+    /// are deleted with the parent record, the rows are wiped, the binaries in the heap are
+    /// cleared, and the ids are removed from the indexes of `fields`
+    pub(crate) fn wipe_records(
+        &self,
+        sig: &TableSig,
+        ids: &BTreeSet<usize>,
+        fields: &[String],
+        change_log: &mut ChangeLog,
+    ) {
+        let info = match self.table_info.iter().find(|info| info.sig == *sig) {
+            Some(info) if info.record_raw_size > 0 => info,
+            _ => return,
+        };
+        let row_length = (info.range.end - info.range.start) / info.record_raw_size;
+        let slots = SlotMap::load(sig, row_length as usize, &self.snapshot);
+        for id in ids.iter() {
+            let row = match slots.row(*id) {
+                Some(row) if row < row_length as usize => row as u32,
+                _ => continue,
+            };
+            let addr = self.table_addr(sig, info.range.start + row * info.record_raw_size);
+            let mut buffer = self.snapshot.load(&addr);
+            if buffer[0] == OVERFLOW_HEADER {
+                let size =
+                    u32::from_be_bytes(buffer[1..5].try_into().expect("load overflow size fail"));
+                clear_heap(sig, *id, size as usize, change_log);
             }
+            buffer[0..HEADER_SIZE as usize].fill(0);
+            change_log.store(&addr, &buffer);
+        }
+        for field in fields.iter() {
+            for id in ids.iter() {
+                update_entry(sig, field, *id, None, &self.snapshot, change_log);
+            }
+        }
+    }
+
+    /// Returns true if the record with the id exists and is not deleted in the table with the
+    /// signature
+    pub(crate) fn record_exists(&self, sig: &TableSig, id: usize) -> bool {
+        let info = match self.table_info.iter().find(|info| info.sig == *sig) {
+            Some(info) if id > 0 && info.record_raw_size > 0 => info,
+            _ => return false,
+        };
+        let row_length = (info.range.end - info.range.start) / info.record_raw_size;
+        match SlotMap::load(sig, row_length as usize, &self.snapshot).row(id) {
+            Some(row) if row < row_length as usize => {
+                let storage_idx = info.range.start + row as u32 * info.record_raw_size;
+                self.snapshot.load(&self.table_addr(sig, storage_idx))[0] != 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Associate the table saved with the `old_name` to the table name of `T`, for example the
+    /// struct is renamed or the table is saved with the type name of struct by previous version,
+    /// the table is not moved and the change is saved when the db is committed.
+    /// ```compile_fail
+    /// let mut db = Db::load(None)?;
+    /// db.rename_table::<Person>("rdb_contract::Person")?;
+    /// db.commit()?;
+    /// ```
+    pub fn rename_table<T: Record>(&mut self, old_name: &str) -> Result<()> {
+        let saved = self
+            .table_sig(old_name)
+            .filter(|sig| self.table_info.iter().any(|info| info.sig == *sig))
+            .ok_or_else(|| Error::TableNotExist(old_name.into()))?;
+        let sig = get_table_signature(T::table_name());
+        if sig != saved {
+            match self.table_sig(T::table_name()) {
+                Some(s) if s == saved => return Ok(()),
+                Some(s) if !self.table_info.iter().any(|info| info.sig == s) => (),
+                _ => return Err(Error::TableSignatureUsed(T::table_name().into()).into()),
+            }
+        }
+        self.aliases.retain(|(_, s)| *s != saved);
+        if sig != saved {
+            self.aliases.push((sig, saved));
+        }
+        Ok(())
+    }
+
+    /// The signature of the table saved with the name, `None` if the table of the name is
+    /// renamed to another name
+    pub(crate) fn table_sig(&self, name: &str) -> Option<TableSig> {
+        let sig = get_table_signature(name);
+        if let Some((_, saved)) = self.aliases.iter().find(|(alias, _)| *alias == sig) {
+            Some(*saved)
+        } else if self.aliases.iter().any(|(_, saved)| *saved == sig) {
+            None
+        } else {
+            Some(sig)
         }
-        self.table_info = new_table_info;
     }
 
     /// get the numbers of tables
@@ -157,8 +396,8 @@ impl Db {
         self.table_info.len()
     }
 
-    pub fn table_info<T>(&self) -> Option<TableInfo> {
-        let sig = get_table_signature(std::any::type_name::<T>());
+    pub fn table_info<T: Record>(&self) -> Option<TableInfo> {
+        let sig = self.table_sig(T::table_name())?;
         for info in self.table_info.iter() {
             if info.sig == sig {
                 return Some(info.clone());
@@ -167,72 +406,106 @@ impl Db {
         None
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn load(_block_height: Option<i64>) -> Result<Self> {
-        unimplemented!()
-    }
-
     /// Import the database from the specific block height
     /// If not the will import db from the latest block
-    #[cfg(target_arch = "wasm32")]
+    ///
+    /// The storage saved in previous version will be upgraded, and the upgraded header is
+    /// committed back if it is loaded from the latest block.
+    ///
+    /// The db and the tables loaded from a past block are read only, the commit of them fails
+    /// with `StorageError::CommitPastVersion` rather than writing the past state over the latest.
     pub fn load(block_height: Option<i64>) -> Result<Self> {
-        if let Some(_block_height) = block_height {
-            unimplemented!();
+        let snapshot = if let Some(block_height) = block_height {
+            Snapshot::at(block_height)?
         } else {
-            let mut db = Self::new()?;
-
-            let config: [u8; 32] = storage_load(&CONFIG_ADDR.into()).bytes;
-
-            if RDB_FEATURE != config[0] {
-                panic!("Sewup feature not correct")
-            }
-
-            if VERSION != config[1] {
-                // TODO data migrate from different version
-                panic!("migration not implement")
-            }
+            Snapshot::latest()
+        };
+        let mut db = Self::new()?;
 
-            db._features =
-                u16::from_le_bytes(config[2..4].try_into().expect("load rdb feature fail"));
+        let config: [u8; 32] = db
+            .load_header(&snapshot)
+            .expect("Sewup feature not correct");
 
-            let mut table_info_size = u32::from_be_bytes(
-                config[28..32]
-                    .try_into()
-                    .expect("load table info length fail"),
-            ) as isize;
+        db._features = u16::from_le_bytes(config[2..4].try_into().expect("load rdb feature fail"));
 
-            let mut addr: [u8; 32] = [0; 32];
-            let mut storage_index = 0;
+        let mut table_info_size = u32::from_be_bytes(
+            config[28..32]
+                .try_into()
+                .expect("load table info length fail"),
+        ) as isize;
 
-            while table_info_size > 0 {
-                storage_index += 1;
-                storage_index_to_addr(storage_index, &mut addr);
+        let mut storage_index = 0;
 
-                let buffer: [u8; 32] = storage_load(&addr.into()).bytes;
-                let mut info =
-                    bincode::deserialize(&buffer[0..16]).expect("load 1st info from chunk fail");
+        while table_info_size > 0 {
+            storage_index += 1;
+            let buffer: [u8; 32] = snapshot.load(&db.namespace.index_addr(storage_index));
+            let mut info =
+                bincode::deserialize(&buffer[0..16]).expect("load 1st info from chunk fail");
+            db.table_info.push(info);
+            if table_info_size > 1 {
+                info =
+                    bincode::deserialize(&buffer[16..32]).expect("load 2nd info from chunk fail");
                 db.table_info.push(info);
-                if table_info_size > 1 {
-                    info = bincode::deserialize(&buffer[16..32])
-                        .expect("load 2nd info from chunk fail");
-                    db.table_info.push(info);
-                }
-                table_info_size = table_info_size - 2;
             }
+            table_info_size = table_info_size - 2;
+        }
+        db.aliases = load_aliases(&snapshot);
+        db.saved_aliases = db.aliases.clone();
+        db.snapshot = snapshot;
+
+        if VERSION != config[1] {
+            db = upgrade(MIGRATIONS, config[1], VERSION, db)?;
+            if block_height.is_none() {
+                db.commit()?;
+            }
+        }
 
-            Ok(db)
+        Ok(db)
+    }
+
+    /// Upgrade the storage saved by the previous version of sewup, and returns the previous
+    /// version if the storage is upgraded.
+    /// This can be called in a `#[ewasm_fn]` to migrate the storage explicitly.
+    pub fn migrate() -> Result<Option<u8>> {
+        match Self::new()?.load_header(&Snapshot::latest()) {
+            Some(config) if VERSION != config[1] => {
+                Self::load(None)?;
+                Ok(Some(config[1]))
+            }
+            _ => Ok(None),
         }
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn commit(&self) -> Result<()> {
-        Ok(())
+    /// Find the header in the namespace of rdb, or in the root space if the db is saved by the
+    /// previous version
+    fn load_header(&mut self, snapshot: &Snapshot) -> Option<[u8; 32]> {
+        for namespace in [Namespace::new(RDB_NAMESPACE), Namespace::root()] {
+            let config = snapshot.load(&namespace.index_addr(0));
+            if config[0] == RDB_FEATURE {
+                self.namespace = namespace;
+                return Some(config);
+            }
+        }
+        None
     }
 
     /// Update the header of Db, but not the Table
     /// The commit of Table will automatically trigger the commit of Db
-    #[cfg(target_arch = "wasm32")]
+    /// The db loaded from a past block can not be committed.
     pub fn commit(&self) -> Result<()> {
+        if let Some(block_height) = self.snapshot.block_height() {
+            return Err(StorageError::CommitPastVersion(block_height).into());
+        }
+        let mut change_log = self.change_log();
+        for table in self.dropped.iter() {
+            self.clear_table(table, &mut change_log);
+        }
+        self.commit_header(&mut change_log)?;
+        change_log.commit();
+        Ok(())
+    }
+
+    pub(crate) fn commit_header(&self, change_log: &mut ChangeLog) -> Result<()> {
         let mut buffer = [0u8; 32];
         RDB_FEATURE.to_be_bytes().swap_with_slice(&mut buffer[0..1]);
         VERSION.to_be_bytes().swap_with_slice(&mut buffer[1..2]);
@@ -240,18 +513,22 @@ impl Db {
             .to_le_bytes()
             .swap_with_slice(&mut buffer[2..4]);
 
-        let mut len_buffer = self.table_info.len().to_be_bytes();
+        let mut len_buffer = (self.table_info.len() as u32).to_be_bytes();
         len_buffer.swap_with_slice(&mut buffer[28..32]);
 
-        storage_store(&CONFIG_ADDR.into(), &buffer.into());
+        let namespace = Namespace::new(RDB_NAMESPACE);
+        change_log.store(&namespace.index_addr(0), &buffer);
+
+        if self.aliases != self.saved_aliases {
+            store_aliases(&self.aliases, &self.saved_aliases, change_log);
+        }
 
-        let mut addr: [u8; 32] = [0; 32];
         let mut storage_index = 0;
 
         let mut iter = self.table_info.chunks_exact(2);
         while storage_index * 32 < self.table_info.len() * 16 {
             storage_index += 1;
-            storage_index_to_addr(storage_index, &mut addr);
+            let addr = namespace.index_addr(storage_index);
 
             if let Some(chunk) = iter.next() {
                 let mut tables =
@@ -260,97 +537,60 @@ impl Db {
                     &mut bincode::serialize(&chunk[1]).expect("serialize 1st info of chunk fail"),
                 );
                 let part: [u8; 32] = tables.try_into().unwrap();
-                storage_store(&addr.into(), &part.into());
+                change_log.store(&addr, &part);
             } else {
                 let remainder = iter.remainder();
                 let mut tables =
                     bincode::serialize(&remainder[0]).expect("serialize 1st info of chunk fail");
                 tables.extend_from_slice(&[0u8; 16]);
                 let part: [u8; 32] = tables.try_into().unwrap();
-                storage_store(&addr.into(), &part.into());
+                change_log.store(&addr, &part);
                 break;
             }
         }
         Ok(())
     }
 
-    #[cfg(any(target_arch = "wasm32", test))]
-    /// alloc storage space for table
-    pub(crate) fn alloc_table_storage(
+    /// Set the number of slots used by the table, and returns the slots used before
+    pub(crate) fn resize_table_storage(
         &mut self,
         sig: TableSig,
         raw_length: u32,
     ) -> Result<Range<u32>> {
-        let mut modify_list: Vec<(Range<u32>, Range<u32>)> = Vec::new();
-        let mut info_raw_length = self.table_info.len() / 2;
-        if self.table_info.len() % 2 > 0 {
-            info_raw_length = info_raw_length + 1;
-        }
-
-        let mut previous_end = (info_raw_length + 1) as u32;
-
-        let mut output: Option<Range<u32>> = None;
-        let mut new_range: Option<Range<u32>> = None;
-        for info in self.table_info.iter_mut() {
-            if info.sig == sig {
-                new_range = Some(Range {
-                    start: previous_end,
-                    end: previous_end + raw_length,
-                });
-                output = new_range.clone();
-            } else if info.range.start != previous_end {
-                new_range = Some(Range {
-                    start: previous_end,
-                    end: previous_end + info.range.end - info.range.start,
-                });
-            }
-
-            if let Some(new_range) = new_range.take() {
-                modify_list.push((info.range.clone(), new_range.clone()));
-                info.range = new_range;
-            }
+        let info = self
+            .table_info
+            .iter_mut()
+            .find(|info| info.sig == sig)
+            .ok_or_else(|| Error::TableNotExist(format!("Table [sig: {:?}]", sig)))?;
+        Ok(std::mem::replace(&mut info.range, 0..raw_length))
+    }
 
-            previous_end = info.range.end;
+    /// The address of the slot of table
+    pub(crate) fn table_addr(&self, sig: &TableSig, idx: u32) -> [u8; 32] {
+        if self.contiguous_tables {
+            self.namespace.index_addr(idx as usize)
+        } else {
+            table_namespace(sig).index_addr(idx as usize)
         }
-
-        migration_table(modify_list)?;
-
-        output.ok_or(Error::TableNotExist(format!("Table [sig: {:?}]", sig)).into())
     }
 }
 
-#[cfg(all(not(target_arch = "wasm32"), test))]
-fn migration_table(mut _list: Vec<(Range<u32>, Range<u32>)>) -> Result<()> {
-    Ok(())
+/// The namespace of the rows of table
+pub(crate) fn table_namespace(sig: &TableSig) -> Namespace {
+    Namespace::new(&format!("{}.{}", RDB_NAMESPACE, hex::encode(sig)))
 }
 
-/// Migrate table from Range to Range
-#[cfg(target_arch = "wasm32")]
-fn migration_table(mut list: Vec<(Range<u32>, Range<u32>)>) -> Result<()> {
-    let mut addr: [u8; 32] = [0; 32];
-
-    while let Some((
-        Range::<u32> {
-            start: _before_range_start,
-            end: before_range_end,
-        },
-        Range::<u32> {
-            start: new_range_start,
-            end: new_range_end,
-        },
-    )) = list.pop()
-    {
-        for i in 1..=new_range_end - new_range_start {
-            storage_index_to_addr((before_range_end - i) as usize, &mut addr);
-            let buffer: [u8; 32] = storage_load(&addr.into()).bytes;
-            storage_index_to_addr((new_range_end - i) as usize, &mut addr);
-            storage_store(&addr.into(), &buffer.into());
-        }
-    }
-    Ok(())
+/// The number of slots of the row of record, which fits the default instance
+pub(crate) fn record_raw_size<T: SerializeTrait + Default + Record>() -> Result<u32> {
+    let size = bincode::serialized_size(&T::default())?;
+    Ok(if size == 0 {
+        0u32
+    } else {
+        (size as u32 + HEADER_SIZE + 31) / 32
+    })
 }
 
-fn get_table_signature(table_name: &str) -> TableSig {
+pub(crate) fn get_table_signature(table_name: &str) -> TableSig {
     let mut sig = [0; 4];
     let mut hasher = Keccak::v256();
     hasher.update(table_name.as_bytes());
@@ -363,7 +603,7 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_alloc_table_storage_with_tables() {
+    fn test_resize_table_storage_with_tables() {
         #[derive(Default, Serialize, Deserialize)]
         struct Person1 {
             trusted: bool,
@@ -383,36 +623,110 @@ mod tests {
         impl Record for Person3 {}
 
         let mut db = Db::default();
-        db.create_table::<Person1>();
-        db.create_table::<Person2>();
-        db.create_table::<Person3>();
+        db.create_table::<Person1>().unwrap();
+        db.create_table::<Person2>().unwrap();
+        db.create_table::<Person3>().unwrap();
         assert!(db.table_info.len() == 3);
         for i in 0..3 {
-            assert!(db.table_info[i].range == Range::<u32> { start: 2, end: 2 });
+            assert!(db.table_info[i].range == Range::<u32> { start: 0, end: 0 });
         }
-        // There are not record in Person1, Person3, and there 3 raw size of records in Person2
+        // There are not record in Person1, Person3, and there 3 raw size of records in Person2,
+        // the other tables are not moved
         let r = db
-            .alloc_table_storage(get_table_signature(std::any::type_name::<Person2>()), 3)
+            .resize_table_storage(get_table_signature(std::any::type_name::<Person2>()), 3)
             .unwrap();
-        assert!(db.table_info[0].range == Range::<u32> { start: 3, end: 3 });
-        assert!(db.table_info[1].range == Range::<u32> { start: 3, end: 6 });
-        assert!(db.table_info[2].range == Range::<u32> { start: 6, end: 6 });
+        assert!(r == Range::<u32> { start: 0, end: 0 });
+        assert!(db.table_info[0].range == Range::<u32> { start: 0, end: 0 });
+        assert!(db.table_info[1].range == Range::<u32> { start: 0, end: 3 });
+        assert!(db.table_info[2].range == Range::<u32> { start: 0, end: 0 });
+
+        let r = db
+            .resize_table_storage(get_table_signature(std::any::type_name::<Person2>()), 1)
+            .unwrap();
+        assert!(r == Range::<u32> { start: 0, end: 3 });
+        assert!(db.table_info[1].range == Range::<u32> { start: 0, end: 1 });
+        assert!(db.resize_table_storage([0u8; 4], 1).is_err());
+
+        let sig = get_table_signature(std::any::type_name::<Person1>());
+        assert_ne!(db.table_addr(&sig, 0), db.namespace.index_addr(0));
+        assert_ne!(
+            db.table_addr(&sig, 0),
+            db.table_addr(&get_table_signature(std::any::type_name::<Person2>()), 0)
+        );
     }
+
     #[test]
-    fn test_alloc_table_storage_with_one_table() {
-        #[derive(Default, Serialize, Deserialize)]
+    fn test_upgrade_contiguous_tables_in_memory() {
+        use crate::storage::{with_backend, MemoryStorage, StorageBackend};
+
+        #[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
         struct Person {
-            trusted: bool,
+            age: u8,
         }
         impl Record for Person {}
 
-        let mut db = Db::default();
-        db.create_table::<Person>();
-        assert!(db.table_info.len() == 1);
-        assert!(db.table_info[0].range == Range::<u32> { start: 2, end: 2 });
-        let r = db.alloc_table_storage(get_table_signature(std::any::type_name::<Person>()), 1);
-
-        assert!(r.unwrap() == Range::<u32> { start: 2, end: 3 });
-        assert!(db.table_info[0].range == Range::<u32> { start: 2, end: 3 });
+        #[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+        struct Pet {
+            legs: u8,
+        }
+        impl Record for Pet {}
+
+        with_backend(|memory| *memory = MemoryStorage::default());
+
+        // The tables of version 1 are saved after the table info in the namespace of rdb
+        let namespace = Namespace::new(RDB_NAMESPACE);
+        let person = get_table_signature(std::any::type_name::<Person>());
+        let pet = get_table_signature(std::any::type_name::<Pet>());
+        let mut header = [0u8; 32];
+        header[0] = RDB_FEATURE;
+        header[1] = 1;
+        header[2] = Feature::Default as u8;
+        header[31] = 2;
+        let mut info = bincode::serialize(&TableInfo {
+            sig: person,
+            range: 2..4,
+            record_raw_size: 1,
+        })
+        .unwrap();
+        info.append(
+            &mut bincode::serialize(&TableInfo {
+                sig: pet,
+                range: 4..5,
+                record_raw_size: 1,
+            })
+            .unwrap(),
+        );
+        let row = |row: Result<crate::types::Row>| row.unwrap().into_raw_vec()[0].to_bytes32();
+        with_backend(|memory| {
+            memory.store(&namespace.index_addr(0), &header);
+            memory.store(&namespace.index_addr(1), &info.try_into().unwrap());
+            memory.store(&namespace.index_addr(2), &row(Person { age: 10 }.to_row(1)));
+            memory.store(&namespace.index_addr(3), &row(Person { age: 20 }.to_row(1)));
+            memory.store(&namespace.index_addr(4), &row(Pet { legs: 4 }.to_row(1)));
+        });
+
+        let db = Db::load(None).unwrap();
+        assert_eq!(db.table_info::<Person>().unwrap().range, 0..2);
+        assert_eq!(db.table_info::<Pet>().unwrap().range, 0..1);
+        assert!(with_backend(
+            |memory| (2..5).all(|i| memory.load(&namespace.index_addr(i)) == [0u8; 32])
+        ));
+        let people = db.table::<Person>().unwrap();
+        assert_eq!(people.get_record(2).unwrap(), Person { age: 20 });
+        let pets = Db::load(None).unwrap().table::<Pet>().unwrap();
+        assert_eq!(pets.get_record(1).unwrap(), Pet { legs: 4 });
+
+        // The commit of the first table does not move the other tables
+        let before = with_backend(|memory| memory.clone());
+        let mut people = Db::load(None).unwrap().table::<Person>().unwrap();
+        people.add_record(Person { age: 30 }).unwrap();
+        people.commit().unwrap();
+        let pet_addr = table_namespace(&pet).index_addr(0);
+        assert_eq!(
+            with_backend(|memory| memory.load(&pet_addr)),
+            before.load(&pet_addr)
+        );
+        let people = Db::load(None).unwrap().table::<Person>().unwrap();
+        assert_eq!(people.all_records().unwrap().len(), 3);
     }
 }
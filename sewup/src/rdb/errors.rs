@@ -3,14 +3,32 @@ use thiserror::Error;
 #[remain::sorted]
 #[derive(Error, Debug, PartialEq)]
 pub enum Error {
+    #[error("`{0}` is not an indexed field")]
+    FieldNotIndexed(String),
+    #[error("`{0}` is not an integer field")]
+    FieldNotInteger(String),
+    #[error("the record is referenced by the foreign key `{0}` of other table")]
+    ForeignKeyRestricted(String),
+    #[error("the record referenced by the foreign key `{0}` does not exist")]
+    ForeignKeyViolation(String),
+    #[error("`{0}` and `{1}` are not the same table")]
+    MigrationTableMismatch(String, String),
     #[error("Record deleted")]
     RecordDeleted,
     #[error("Record Id not correct, it starts from 1 not zero")]
     RecordIdCorrect,
     #[error("Record is not fixed, and overflowed")]
     RecordNotSized,
+    #[error("the records of `{0}` are saved with schema version {1}, please migrate the table")]
+    SchemaVersionMismatch(String, u32),
     #[error("No record in table")]
     TableIsEmpty,
     #[error("`{0}` did not exist")]
     TableNotExist(String),
+    #[error("the signature of `{0}` is used by another table")]
+    TableSignatureUsed(String),
+    #[error("the value of unique field `{0}` already exists")]
+    UniqueViolation(String),
+    #[error("`{0}` is not a field of record")]
+    UnknownField(String),
 }
@@ -0,0 +1,92 @@
+//! The overflow heap of table, which keeps the binary of the records not fitting the fixed size
+//! of rows, such as the records with `String` or `Vec` fields.
+//!
+//! The row of an overflowed record in the table is a pointer, which has the
+//! `OVERFLOW_HEADER` and the size of binary, and the binary is saved in the slots addressed by
+//! the hash of table signature and record id, so it is not moved when the storage of tables is
+//! reallocated.
+//! ```compile_fail
+//! | address                      | 0th ~ 31st bytes                |
+//! |------------------------------|---------------------------------|
+//! | sha3("heap", sig, id, i)     | the i-th part of record binary  |
+//! ```
+use std::collections::{BTreeSet, HashMap};
+
+use crate::journal::{ChangeLog, Snapshot};
+use crate::rdb::db::TableSig;
+use crate::utils::sha3_256;
+
+fn heap_addr(sig: &TableSig, id: usize, idx: u32) -> [u8; 32] {
+    let mut input: Vec<u8> = "heap".as_bytes().into();
+    input.extend_from_slice(sig);
+    input.extend_from_slice(&(id as u32).to_be_bytes());
+    input.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&input)
+}
+
+fn slot_length(size: usize) -> u32 {
+    ((size + 31) / 32) as u32
+}
+
+/// Clear the saved binary of the record, for example the table is dropped
+pub(crate) fn clear_heap(sig: &TableSig, id: usize, size: usize, change_log: &mut ChangeLog) {
+    for i in 0..slot_length(size) {
+        change_log.store(&heap_addr(sig, id, i), &[0u8; 32]);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Heap {
+    binaries: HashMap<usize, Vec<u8>>,
+    /// The number of slots of the binaries on load, the unused slots are cleared on commit
+    loaded: HashMap<usize, u32>,
+    dirty: BTreeSet<usize>,
+}
+
+impl Heap {
+    /// Load the binary of the record from the snapshot
+    pub(crate) fn load(&mut self, sig: &TableSig, id: usize, size: usize, snapshot: &Snapshot) {
+        let length = slot_length(size);
+        let mut bin: Vec<u8> = Vec::with_capacity(length as usize * 32);
+        for i in 0..length {
+            bin.extend_from_slice(&snapshot.load(&heap_addr(sig, id, i)));
+        }
+        bin.truncate(size);
+        self.binaries.insert(id, bin);
+        self.loaded.insert(id, length);
+    }
+
+    pub(crate) fn get(&self, id: usize) -> Option<&Vec<u8>> {
+        self.binaries.get(&id)
+    }
+
+    pub(crate) fn insert(&mut self, id: usize, bin: Vec<u8>) {
+        self.binaries.insert(id, bin);
+        self.dirty.insert(id);
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) {
+        if self.binaries.remove(&id).is_some() {
+            self.dirty.insert(id);
+        }
+    }
+
+    /// Save the modified binaries, and clear the slots no longer used
+    pub(crate) fn commit(&mut self, sig: &TableSig, change_log: &mut ChangeLog) {
+        for id in std::mem::take(&mut self.dirty).into_iter() {
+            let mut length = 0;
+            if let Some(bin) = self.binaries.get(&id) {
+                length = slot_length(bin.len());
+                for (i, chunk) in bin.chunks(32).enumerate() {
+                    let mut buffer = [0u8; 32];
+                    buffer[0..chunk.len()].copy_from_slice(chunk);
+                    change_log.store(&heap_addr(sig, id, i as u32), &buffer);
+                }
+            }
+            for i in length..self.loaded.get(&id).copied().unwrap_or_default() {
+                change_log.store(&heap_addr(sig, id, i), &[0u8; 32]);
+            }
+            self.loaded.insert(id, length);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Secondary index of table, which keeps the ids of records in the order of an indexed field,
+//! such that the equality and range filters on the field do not compare every record.
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::ops::{Bound, RangeBounds};
+
+use crate::journal::{ChangeLog, Snapshot};
+use crate::rdb::db::TableSig;
+use crate::utils::sha3_256;
+
+/// The ordered bytes of field and the id of record
+type Entries = Vec<(Vec<u8>, usize)>;
+
+/// The bytes of key kept in the first slot of entry
+const FIRST_CHUNK_SIZE: usize = 28;
+
+/// The entries of the index of a field are saved in the slots addressed by the hash of table
+/// signature, field name and the id of record, so only the entries of the changed records are
+/// written on commit, and the entries are not moved when the storage of tables is reallocated.
+/// The header of id 0 keeps the saved flag in the 0th byte and the largest id in the 28th ~ 31st
+/// bytes (BE), and the size is 0 if the record has no entry.
+/// ```compile_fail
+/// | address                          | 0th ~ 3rd            | 4th ~ 31st                   |
+/// |----------------------------------|----------------------|------------------------------|
+/// | sha3("index", sig, field, 0, 0)  | the header of index                                 |
+/// | sha3("index", sig, field, id, 0) | size of key + 1 (BE) | the first 28 bytes of key    |
+/// | sha3("index", sig, field, id, i) | the following bytes of key                          |
+/// ```
+fn index_addr(sig: &TableSig, field: &str, id: usize, idx: u32) -> [u8; 32] {
+    let mut input: Vec<u8> = "index".as_bytes().into();
+    input.extend_from_slice(sig);
+    input.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    input.extend_from_slice(field.as_bytes());
+    input.extend_from_slice(&(id as u32).to_be_bytes());
+    input.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&input)
+}
+
+/// The number of slots of the entry with the key of `size`
+fn entry_slot_length(size: usize) -> usize {
+    1 + (size.saturating_sub(FIRST_CHUNK_SIZE) + 31) / 32
+}
+
+/// Load the largest id of the saved index, `None` if the index is never saved
+fn load_header(sig: &TableSig, field: &str, snapshot: &Snapshot) -> Option<u32> {
+    let header = snapshot.load(&index_addr(sig, field, 0, 0));
+    if header[0] == 0 {
+        return None;
+    }
+    Some(u32::from_be_bytes(
+        header[28..32].try_into().expect("load index header fail"),
+    ))
+}
+
+fn store_header(sig: &TableSig, field: &str, id_length: u32, change_log: &mut ChangeLog) {
+    let mut header = [0u8; 32];
+    header[0] = 1;
+    header[28..32].copy_from_slice(&id_length.to_be_bytes());
+    change_log.store(&index_addr(sig, field, 0, 0), &header);
+}
+
+/// Load the key of the record in the saved index, `None` if the record has no entry
+fn load_entry(sig: &TableSig, field: &str, id: usize, snapshot: &Snapshot) -> Option<Vec<u8>> {
+    let first = snapshot.load(&index_addr(sig, field, id, 0));
+    let size = u32::from_be_bytes(first[0..4].try_into().expect("load index entry size fail"));
+    if size == 0 {
+        return None;
+    }
+    let size = size as usize - 1;
+    let mut key: Vec<u8> = Vec::with_capacity(size + 31);
+    key.extend_from_slice(&first[4..32]);
+    for i in 1..entry_slot_length(size) {
+        key.extend_from_slice(&snapshot.load(&index_addr(sig, field, id, i as u32)));
+    }
+    key.truncate(size);
+    Some(key)
+}
+
+/// Save the key of the record, the entry is removed if the key is `None`, and the slots of the
+/// previous entry no longer used are cleared
+fn store_entry(
+    sig: &TableSig,
+    field: &str,
+    id: usize,
+    key: Option<&[u8]>,
+    previous_slot_length: usize,
+    change_log: &mut ChangeLog,
+) {
+    let slot_length = match key {
+        Some(key) => {
+            let split = key.len().min(FIRST_CHUNK_SIZE);
+            let mut buffer = [0u8; 32];
+            buffer[0..4].copy_from_slice(&(key.len() as u32 + 1).to_be_bytes());
+            buffer[4..4 + split].copy_from_slice(&key[0..split]);
+            change_log.store(&index_addr(sig, field, id, 0), &buffer);
+            for (i, chunk) in key[split..].chunks(32).enumerate() {
+                let mut buffer = [0u8; 32];
+                buffer[0..chunk.len()].copy_from_slice(chunk);
+                change_log.store(&index_addr(sig, field, id, i as u32 + 1), &buffer);
+            }
+            entry_slot_length(key.len())
+        }
+        None => 0,
+    };
+    for i in slot_length..previous_slot_length {
+        change_log.store(&index_addr(sig, field, id, i as u32), &[0u8; 32]);
+    }
+}
+
+/// Load the sorted entries of index and the largest id, `None` if the index is never saved
+fn load_entries(sig: &TableSig, field: &str, snapshot: &Snapshot) -> Option<(Entries, u32)> {
+    let id_length = load_header(sig, field, snapshot)?;
+    let mut entries: Entries = (1..=id_length as usize)
+        .filter_map(|id| load_entry(sig, field, id, snapshot).map(|key| (key, id)))
+        .collect();
+    entries.sort();
+    Some((entries, id_length))
+}
+
+/// Clear the saved index of field, for example the table is dropped
+pub(crate) fn clear_index(
+    sig: &TableSig,
+    field: &str,
+    snapshot: &Snapshot,
+    change_log: &mut ChangeLog,
+) {
+    if let Some(id_length) = load_header(sig, field, snapshot) {
+        for id in 1..=id_length as usize {
+            if let Some(key) = load_entry(sig, field, id, snapshot) {
+                store_entry(
+                    sig,
+                    field,
+                    id,
+                    None,
+                    entry_slot_length(key.len()),
+                    change_log,
+                );
+            }
+        }
+        change_log.store(&index_addr(sig, field, 0, 0), &[0u8; 32]);
+    }
+}
+
+/// The ids of the records of the table with one of the keys in the saved index of field
+pub(crate) fn ids_of_keys(
+    sig: &TableSig,
+    field: &str,
+    keys: &[Vec<u8>],
+    snapshot: &Snapshot,
+) -> Vec<usize> {
+    let entries = match load_entries(sig, field, snapshot) {
+        Some((entries, _)) => entries,
+        None => return Vec::new(),
+    };
+    let mut ids = Vec::new();
+    for key in keys.iter() {
+        let start = entries.partition_point(|(k, _)| k < key);
+        ids.extend(
+            entries[start..]
+                .iter()
+                .take_while(|(k, _)| k == key)
+                .map(|(_, id)| *id),
+        );
+    }
+    ids
+}
+
+/// Replace the key of the record in the saved index of field without loading the table, the
+/// entry is removed if the key is `None`, for example the child records are deleted or detached
+/// by the deletion of parent records
+pub(crate) fn update_entry(
+    sig: &TableSig,
+    field: &str,
+    id: usize,
+    key: Option<&[u8]>,
+    snapshot: &Snapshot,
+    change_log: &mut ChangeLog,
+) {
+    let id_length = match load_header(sig, field, snapshot) {
+        Some(id_length) => id_length,
+        None => return,
+    };
+    let previous = load_entry(sig, field, id, snapshot);
+    if previous.as_deref() == key {
+        return;
+    }
+    let previous_slot_length = previous.map(|k| entry_slot_length(k.len())).unwrap_or(0);
+    store_entry(sig, field, id, key, previous_slot_length, change_log);
+    if key.is_some() && id as u32 > id_length {
+        store_header(sig, field, id as u32, change_log);
+    }
+}
+
+/// The entries of index are the ordered bytes of field and the id of record, which are sorted by
+/// the bytes and then the id.
+pub(crate) struct Index {
+    pub(crate) field: &'static str,
+    pub(crate) unique: bool,
+    entries: Entries,
+    /// The largest id of the saved index, `None` if the index is never saved
+    id_length: Option<u32>,
+    /// The ids of the changed entries and the number of slots of the saved entries, the entries
+    /// are written and the unused slots are cleared on commit
+    dirty: BTreeMap<usize, usize>,
+}
+
+impl Index {
+    /// Load the index of field from the snapshot, `None` if the index is never saved
+    pub(crate) fn load(
+        sig: &TableSig,
+        field: &'static str,
+        unique: bool,
+        snapshot: &Snapshot,
+    ) -> Option<Self> {
+        let (entries, id_length) = load_entries(sig, field, snapshot)?;
+        Some(Self {
+            field,
+            unique,
+            entries,
+            id_length: Some(id_length),
+            dirty: BTreeMap::new(),
+        })
+    }
+
+    /// Build the index from the ordered bytes of field of the records
+    pub(crate) fn build(
+        field: &'static str,
+        unique: bool,
+        keys: impl Iterator<Item = (Vec<u8>, usize)>,
+    ) -> Self {
+        let mut index = Self {
+            field,
+            unique,
+            entries: Vec::new(),
+            id_length: None,
+            dirty: BTreeMap::new(),
+        };
+        index.rebuild(keys);
+        index
+    }
+
+    /// Replace the entries with the ordered bytes of field of the records, for example the
+    /// records are migrated to another schema
+    pub(crate) fn rebuild(&mut self, keys: impl Iterator<Item = (Vec<u8>, usize)>) {
+        for (key, id) in std::mem::take(&mut self.entries).into_iter() {
+            self.mark_dirty(id, Some(&key));
+        }
+        self.entries = keys.collect();
+        self.entries.sort();
+        let ids: Vec<usize> = self.entries.iter().map(|(_, id)| *id).collect();
+        for id in ids.into_iter() {
+            self.mark_dirty(id, None);
+        }
+    }
+
+    /// Keep the number of slots of the saved entry of record before the first change, the
+    /// entries not changed are saved as the `entries`
+    fn mark_dirty(&mut self, id: usize, saved_key: Option<&[u8]>) {
+        let saved = self.id_length.is_some();
+        self.dirty.entry(id).or_insert_with(|| match saved_key {
+            Some(key) if saved => entry_slot_length(key.len()),
+            _ => 0,
+        });
+    }
+
+    /// Save the changed entries, the index is saved even it is empty, such that it is not built
+    /// again on load
+    pub(crate) fn commit(&mut self, sig: &TableSig, change_log: &mut ChangeLog) {
+        if self.id_length.is_some() && self.dirty.is_empty() {
+            return;
+        }
+        let dirty = std::mem::take(&mut self.dirty);
+        let mut keys: BTreeMap<usize, &[u8]> = BTreeMap::new();
+        for (key, id) in self.entries.iter() {
+            if dirty.contains_key(id) {
+                keys.insert(*id, key);
+            }
+        }
+        let mut id_length = self.id_length.unwrap_or_default();
+        for (id, previous_slot_length) in dirty.into_iter() {
+            let key = keys.get(&id).copied();
+            store_entry(sig, self.field, id, key, previous_slot_length, change_log);
+            if key.is_some() {
+                id_length = id_length.max(id as u32);
+            }
+        }
+        if self.id_length != Some(id_length) {
+            store_header(sig, self.field, id_length, change_log);
+            self.id_length = Some(id_length);
+        }
+    }
+
+    /// Returns true if there is a record other than `id` with the key
+    pub(crate) fn conflicts(&self, key: &[u8], id: usize) -> bool {
+        let start = self.entries.partition_point(|(k, _)| k.as_slice() < key);
+        self.entries[start..]
+            .iter()
+            .take_while(|(k, _)| k.as_slice() == key)
+            .any(|(_, i)| *i != id)
+    }
+
+    pub(crate) fn insert(&mut self, key: Vec<u8>, id: usize) {
+        let entry = (key, id);
+        if let Err(pos) = self.entries.binary_search(&entry) {
+            self.entries.insert(pos, entry);
+            self.mark_dirty(id, None);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: Vec<u8>, id: usize) {
+        if let Ok(pos) = self.entries.binary_search(&(key, id)) {
+            let (key, _) = self.entries.remove(pos);
+            self.mark_dirty(id, Some(&key));
+        }
+    }
+
+    /// The ids of records with the key in the range, in the order of keys
+    pub(crate) fn ids<R: RangeBounds<Vec<u8>>>(&self, range: R) -> Vec<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(s) => self.entries.partition_point(|(k, _)| k < s),
+            Bound::Excluded(s) => self.entries.partition_point(|(k, _)| k <= s),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(e) => self.entries.partition_point(|(k, _)| k <= e),
+            Bound::Excluded(e) => self.entries.partition_point(|(k, _)| k < e),
+            Bound::Unbounded => self.entries.len(),
+        };
+        if start >= end {
+            return Vec::new();
+        }
+        self.entries[start..end].iter().map(|(_, id)| *id).collect()
+    }
+}
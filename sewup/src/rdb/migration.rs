@@ -0,0 +1,70 @@
+//! The upgrade steps of the storage format of rdb
+use anyhow::Result;
+
+use crate::migration::Migration;
+use crate::rdb::db::table_namespace;
+use crate::rdb::Db;
+use crate::storage::{load as storage_load, Namespace, RDB_NAMESPACE};
+
+/// The upgrade steps for the db saved by the previous versions, the step can also move the data
+/// of tables in the storage
+pub(crate) const MIGRATIONS: &[Migration<Db>] = &[
+    Migration {
+        from: 0,
+        upgrade: move_into_namespace,
+    },
+    Migration {
+        from: 1,
+        upgrade: move_into_table_namespaces,
+    },
+];
+
+/// The db of version 0 is saved in the root space, the tables are moved into the namespace of rdb
+/// and the slots in the root space are cleared, then the header will be committed into the
+/// namespace after upgrading.
+/// The db loaded from a past block is still read from the root space.
+fn move_into_namespace(mut db: Db) -> Result<Db> {
+    let root = Namespace::root();
+    if db.snapshot.block_height().is_some() || db.namespace != root {
+        return Ok(db);
+    }
+    let namespace = Namespace::new(RDB_NAMESPACE);
+    let mut change_log = db.change_log();
+    for info in db.table_info.iter() {
+        for idx in info.range.clone() {
+            let addr = root.index_addr(idx as usize);
+            let value = storage_load(&addr);
+            change_log.store(&namespace.index_addr(idx as usize), &value);
+            change_log.store(&addr, &[0u8; 32]);
+        }
+    }
+    for idx in 0..=(db.table_info.len() + 1) / 2 {
+        change_log.store(&root.index_addr(idx), &[0u8; 32]);
+    }
+    change_log.commit();
+    db.namespace = namespace;
+    Ok(db)
+}
+
+/// The tables of version 1 are saved after the table info, and the commit of a table moves the
+/// tables after it, the tables are moved into the namespaces of tables and the slots are cleared.
+/// The db loaded from a past block is still read from the contiguous slots.
+fn move_into_table_namespaces(mut db: Db) -> Result<Db> {
+    if db.snapshot.block_height().is_some() {
+        db.contiguous_tables = true;
+        return Ok(db);
+    }
+    let mut change_log = db.change_log();
+    for info in db.table_info.iter_mut() {
+        let namespace = table_namespace(&info.sig);
+        for (i, idx) in info.range.clone().enumerate() {
+            let addr = db.namespace.index_addr(idx as usize);
+            let value = storage_load(&addr);
+            change_log.store(&namespace.index_addr(i), &value);
+            change_log.store(&addr, &[0u8; 32]);
+        }
+        info.range = 0..(info.range.end - info.range.start);
+    }
+    change_log.commit();
+    Ok(db)
+}
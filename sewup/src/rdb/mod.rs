@@ -5,6 +5,11 @@
 #[derive(PartialEq)]
 pub enum Feature {
     Default = 1,
+    /// Keep the change log of commits, such that the db can be loaded as of an earlier block
+    Versioning = 2,
+    /// Reuse the rows of deleted records when the records are added, the ids of deleted records
+    /// are never reused
+    ReuseRows = 4,
 }
 
 mod db;
@@ -13,6 +18,28 @@ pub use db::*;
 mod table;
 pub use table::*;
 
+mod transaction;
+pub use transaction::*;
+
+mod heap;
+
+mod index;
+
+pub mod query;
+
+mod relation;
+
+mod slots;
+
+mod schema;
+
+mod alias;
+
+mod migration;
+
 pub mod traits;
 
 pub mod errors;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,189 @@
+//! The query model of table, which is serialized in the protocol of the `Table` derive, such that
+//! the clients can query the records with conditions, order, limit and offset.
+//!
+//! The values in conditions are kept in the order-preserving encoding of
+//! [`to_ordered_bytes`](crate::ordered::to_ordered_bytes), so the conditions are evaluated by
+//! comparing bytes, and the indexes of fields are used if available.
+//! ```compile_fail
+//! let mut query = person::Protocol::default();
+//! query.add_predicate("age", Condition::between(&18u8, &65u8));
+//! query.add_predicate("trusted", Condition::equal(&false).not());
+//! query.set_order_by("age", Order::Desc);
+//! query.set_limit(10);
+//! ```
+//!
+//! The records matching the conditions can be aggregated by `Aggregate`, and grouped by the
+//! value of a field, such that the clients get the aggregates without downloading the records.
+//! ```compile_fail
+//! let mut query = person::Protocol::default();
+//! query.add_predicate("trusted", Condition::equal(&true));
+//! query.set_group_by("age", Aggregate::Count);
+//! ```
+use std::ops::Bound;
+
+use crate::ordered::to_ordered_bytes;
+use crate::rdb::errors::Error;
+use crate::{Deserialize, Serialize, SerializeTrait, Value};
+use anyhow::Result;
+
+fn encode<V: SerializeTrait>(value: &V) -> Vec<u8> {
+    to_ordered_bytes(value).expect("the value can not be encoded in order")
+}
+
+/// The condition on the value of a field
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    Equal(Vec<u8>),
+    Greater(Vec<u8>),
+    GreaterOrEqual(Vec<u8>),
+    Less(Vec<u8>),
+    LessOrEqual(Vec<u8>),
+    /// The value in the inclusive range
+    Between(Vec<u8>, Vec<u8>),
+    /// The value is one of the list
+    In(Vec<Vec<u8>>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn equal<V: SerializeTrait>(value: &V) -> Self {
+        Condition::Equal(encode(value))
+    }
+
+    pub fn gt<V: SerializeTrait>(value: &V) -> Self {
+        Condition::Greater(encode(value))
+    }
+
+    pub fn ge<V: SerializeTrait>(value: &V) -> Self {
+        Condition::GreaterOrEqual(encode(value))
+    }
+
+    pub fn lt<V: SerializeTrait>(value: &V) -> Self {
+        Condition::Less(encode(value))
+    }
+
+    pub fn le<V: SerializeTrait>(value: &V) -> Self {
+        Condition::LessOrEqual(encode(value))
+    }
+
+    pub fn between<V: SerializeTrait>(low: &V, high: &V) -> Self {
+        Condition::Between(encode(low), encode(high))
+    }
+
+    pub fn one_of<V: SerializeTrait>(values: &[V]) -> Self {
+        Condition::In(values.iter().map(encode).collect())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Condition::Not(Box::new(self))
+    }
+
+    /// Check the ordered bytes of the value of field
+    pub fn matches(&self, key: &[u8]) -> bool {
+        match self {
+            Condition::Equal(v) => key == v.as_slice(),
+            Condition::Greater(v) => key > v.as_slice(),
+            Condition::GreaterOrEqual(v) => key >= v.as_slice(),
+            Condition::Less(v) => key < v.as_slice(),
+            Condition::LessOrEqual(v) => key <= v.as_slice(),
+            Condition::Between(low, high) => low.as_slice() <= key && key <= high.as_slice(),
+            Condition::In(values) => values.iter().any(|v| key == v.as_slice()),
+            Condition::Not(c) => !c.matches(key),
+        }
+    }
+
+    /// The ranges of ordered bytes to look up in the index, `None` if the index can not be used
+    pub(crate) fn index_ranges(&self) -> Option<Vec<(Bound<Vec<u8>>, Bound<Vec<u8>>)>> {
+        let ranges = match self {
+            Condition::Equal(v) => vec![(Bound::Included(v.clone()), Bound::Included(v.clone()))],
+            Condition::Greater(v) => vec![(Bound::Excluded(v.clone()), Bound::Unbounded)],
+            Condition::GreaterOrEqual(v) => vec![(Bound::Included(v.clone()), Bound::Unbounded)],
+            Condition::Less(v) => vec![(Bound::Unbounded, Bound::Excluded(v.clone()))],
+            Condition::LessOrEqual(v) => vec![(Bound::Unbounded, Bound::Included(v.clone()))],
+            Condition::Between(low, high) => {
+                vec![(Bound::Included(low.clone()), Bound::Included(high.clone()))]
+            }
+            Condition::In(values) => values
+                .iter()
+                .map(|v| (Bound::Included(v.clone()), Bound::Included(v.clone())))
+                .collect(),
+            Condition::Not(_) => return None,
+        };
+        Some(ranges)
+    }
+}
+
+/// The condition on a field of record
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Predicate {
+    pub field: String,
+    pub condition: Condition,
+}
+
+impl Predicate {
+    pub fn new(field: &str, condition: Condition) -> Self {
+        Self {
+            field: field.into(),
+            condition,
+        }
+    }
+}
+
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// The aggregate of the records, the field of `Sum`, `Min` and `Max` should be an integer or an
+/// optional integer, and the records with `None` are skipped.
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Aggregate {
+    Count,
+    Sum(String),
+    Min(String),
+    Max(String),
+}
+
+impl Aggregate {
+    /// The field aggregated, `None` for `Count`
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Aggregate::Count => None,
+            Aggregate::Sum(f) | Aggregate::Min(f) | Aggregate::Max(f) => Some(f),
+        }
+    }
+
+    /// Aggregate the values of field, the `Sum` of no value is zero, and the `Min` and `Max` of
+    /// no value are `None`
+    pub(crate) fn apply<I: Iterator<Item = i128>>(&self, values: I) -> Option<i128> {
+        match self {
+            Aggregate::Count => Some(values.count() as i128),
+            Aggregate::Sum(_) => Some(values.sum()),
+            Aggregate::Min(_) => values.min(),
+            Aggregate::Max(_) => values.max(),
+        }
+    }
+}
+
+/// The integer of the field value, `None` for the value `None`, or `FieldNotInteger`
+pub(crate) fn integer_of(field: &str, value: Value) -> Result<Option<i128>> {
+    Ok(match value {
+        Value::U8(i) => Some(i.into()),
+        Value::U16(i) => Some(i.into()),
+        Value::U32(i) => Some(i.into()),
+        Value::U64(i) => Some(i.into()),
+        Value::I8(i) => Some(i.into()),
+        Value::I16(i) => Some(i.into()),
+        Value::I32(i) => Some(i.into()),
+        Value::I64(i) => Some(i.into()),
+        Value::Option(None) => None,
+        Value::Option(Some(v)) | Value::Newtype(v) => return integer_of(field, *v),
+        _ => return Err(Error::FieldNotInteger(field.into()).into()),
+    })
+}
@@ -0,0 +1,186 @@
+//! The relations of table, which are registered by the child tables with the foreign keys, such
+//! that the children are checked, deleted or detached when the records of parent table are
+//! deleted.
+//!
+//! The relations are saved in the slots addressed by the hash of the signature of parent table,
+//! so they are not moved when the storage of tables is reallocated.
+//! ```compile_fail
+//! | address                      | 0th ~ 27th | 28th ~ 31st            |
+//! |------------------------------|------------|------------------------|
+//! | sha3("relation", sig, 0)     | -          | size of binary (BE)    |
+//! | sha3("relation", sig, i)     | the binary of relations             |
+//! ```
+//!
+//! The child records detached by `OnDelete::SetNone` are kept in the slots addressed by the hash
+//! of the signature of child table, until the records are rewritten by the commit of child table.
+//! ```compile_fail
+//! | address                      | 0th ~ 27th | 28th ~ 31st            |
+//! |------------------------------|------------|------------------------|
+//! | sha3("detached", sig, 0)     | -          | size of binary (BE)    |
+//! | sha3("detached", sig, i)     | the binary of foreign keys and ids  |
+//! ```
+use std::convert::TryInto;
+
+use crate::journal::{ChangeLog, Snapshot};
+use crate::rdb::db::TableSig;
+use crate::rdb::traits::OnDelete;
+use crate::utils::sha3_256;
+use crate::{Deserialize, Serialize};
+
+fn relation_addr(sig: &TableSig, idx: u32) -> [u8; 32] {
+    let mut input: Vec<u8> = "relation".as_bytes().into();
+    input.extend_from_slice(sig);
+    input.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&input)
+}
+
+fn detached_addr(sig: &TableSig, idx: u32) -> [u8; 32] {
+    let mut input: Vec<u8> = "detached".as_bytes().into();
+    input.extend_from_slice(sig);
+    input.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&input)
+}
+
+/// The foreign key of child table referencing the parent table
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Relation {
+    pub(crate) child: TableSig,
+    pub(crate) field: String,
+    pub(crate) on_delete: OnDelete,
+    /// The indexed fields of child table, the ids of the child records deleted by the parent are
+    /// removed from these indexes
+    pub(crate) indexes: Vec<String>,
+}
+
+/// Load the binary saved by `store_binary`, which is empty if nothing is saved
+fn load_binary(addr: impl Fn(u32) -> [u8; 32], snapshot: &Snapshot) -> Vec<u8> {
+    let header = snapshot.load(&addr(0));
+    let size = u32::from_be_bytes(header[28..32].try_into().expect("load binary size fail"));
+    let mut bin: Vec<u8> = Vec::with_capacity(size as usize + 31);
+    for i in 1..=(size + 31) / 32 {
+        bin.extend_from_slice(&snapshot.load(&addr(i)));
+    }
+    bin.truncate(size as usize);
+    bin
+}
+
+/// Save the binary, and clear the slots of the previous binary no longer used, all the slots are
+/// cleared if the binary is empty
+fn store_binary(
+    addr: impl Fn(u32) -> [u8; 32],
+    bin: &[u8],
+    previous_size: usize,
+    change_log: &mut ChangeLog,
+) {
+    let mut header = [0u8; 32];
+    header[28..32].copy_from_slice(&(bin.len() as u32).to_be_bytes());
+    change_log.store(&addr(0), &header);
+
+    let slot_length = (bin.len() as u32 + 31) / 32;
+    for (i, chunk) in bin.chunks(32).enumerate() {
+        let mut buffer = [0u8; 32];
+        buffer[0..chunk.len()].copy_from_slice(chunk);
+        change_log.store(&addr(i as u32 + 1), &buffer);
+    }
+    for i in slot_length..(previous_size as u32 + 31) / 32 {
+        change_log.store(&addr(i + 1), &[0u8; 32]);
+    }
+}
+
+/// Load the relations of the children of the parent table
+pub(crate) fn load_relations(parent: &TableSig, snapshot: &Snapshot) -> Vec<Relation> {
+    let bin = load_binary(|i| relation_addr(parent, i), snapshot);
+    if bin.is_empty() {
+        return Vec::new();
+    }
+    bincode::deserialize(&bin).expect("load relations fail")
+}
+
+/// Save the relations of the parent table
+fn store_relations(
+    parent: &TableSig,
+    relations: &[Relation],
+    snapshot: &Snapshot,
+    change_log: &mut ChangeLog,
+) {
+    let previous_size = load_binary(|i| relation_addr(parent, i), snapshot).len();
+    let bin = if relations.is_empty() {
+        Vec::new()
+    } else {
+        bincode::serialize(relations).expect("serialize relations fail")
+    };
+    store_binary(
+        |i| relation_addr(parent, i),
+        &bin,
+        previous_size,
+        change_log,
+    );
+}
+
+/// Register the relation to the parent table, the relation of the same foreign key is replaced
+pub(crate) fn register_relation(
+    parent: &TableSig,
+    relation: Relation,
+    snapshot: &Snapshot,
+    change_log: &mut ChangeLog,
+) {
+    let mut relations = load_relations(parent, snapshot);
+    if relations.contains(&relation) {
+        return;
+    }
+    relations.retain(|r| r.child != relation.child || r.field != relation.field);
+    relations.push(relation);
+    store_relations(parent, &relations, snapshot, change_log);
+}
+
+/// Remove the relations of the child table from the parent table, for example the child table is
+/// dropped
+pub(crate) fn unregister_relations(
+    parent: &TableSig,
+    child: &TableSig,
+    snapshot: &Snapshot,
+    change_log: &mut ChangeLog,
+) {
+    let mut relations = load_relations(parent, snapshot);
+    let length = relations.len();
+    relations.retain(|r| r.child != *child);
+    if relations.len() != length {
+        store_relations(parent, &relations, snapshot, change_log);
+    }
+}
+
+/// Clear the relations of the parent table, for example the parent table is dropped
+pub(crate) fn clear_relations(parent: &TableSig, snapshot: &Snapshot, change_log: &mut ChangeLog) {
+    if !load_relations(parent, snapshot).is_empty() {
+        store_relations(parent, &[], snapshot, change_log);
+    }
+}
+
+/// Load the foreign keys and the ids of the child records detached by the deletion of parent
+/// records, which are not rewritten yet
+pub(crate) fn load_detached(child: &TableSig, snapshot: &Snapshot) -> Vec<(String, usize)> {
+    let bin = load_binary(|i| detached_addr(child, i), snapshot);
+    if bin.is_empty() {
+        return Vec::new();
+    }
+    bincode::deserialize(&bin).expect("load detached records fail")
+}
+
+/// Save the detached child records, the slots are cleared if `detached` is empty
+pub(crate) fn store_detached(
+    child: &TableSig,
+    detached: &[(String, usize)],
+    snapshot: &Snapshot,
+    change_log: &mut ChangeLog,
+) {
+    let previous_size = load_binary(|i| detached_addr(child, i), snapshot).len();
+    if previous_size == 0 && detached.is_empty() {
+        return;
+    }
+    let bin = if detached.is_empty() {
+        Vec::new()
+    } else {
+        bincode::serialize(detached).expect("serialize detached records fail")
+    };
+    store_binary(|i| detached_addr(child, i), &bin, previous_size, change_log);
+}
@@ -0,0 +1,41 @@
+//! The schema version of table, which is the `Record::SCHEMA_VERSION` of the records saved in
+//! the table, such that the records are not decoded with a struct of another version.
+//!
+//! The version is saved in the slot addressed by the hash of table signature, the tables never
+//! saved with a version are version 0.
+//! ```compile_fail
+//! | address             | 0th ~ 27th | 28th ~ 31st         |
+//! |---------------------|------------|---------------------|
+//! | sha3("schema", sig) | -          | schema version (BE) |
+//! ```
+use std::convert::TryInto;
+
+use crate::journal::{ChangeLog, Snapshot};
+use crate::rdb::db::TableSig;
+use crate::utils::sha3_256;
+
+fn schema_addr(sig: &TableSig) -> [u8; 32] {
+    let mut input: Vec<u8> = "schema".as_bytes().into();
+    input.extend_from_slice(sig);
+    sha3_256(&input)
+}
+
+/// Load the schema version of the table
+pub(crate) fn load_schema_version(sig: &TableSig, snapshot: &Snapshot) -> u32 {
+    let buffer = snapshot.load(&schema_addr(sig));
+    u32::from_be_bytes(buffer[28..32].try_into().expect("load schema version fail"))
+}
+
+/// Save the schema version of the table if it is changed
+pub(crate) fn store_schema_version(
+    sig: &TableSig,
+    version: u32,
+    snapshot: &Snapshot,
+    change_log: &mut ChangeLog,
+) {
+    if load_schema_version(sig, snapshot) != version {
+        let mut buffer = [0u8; 32];
+        buffer[28..32].copy_from_slice(&version.to_be_bytes());
+        change_log.store(&schema_addr(sig), &buffer);
+    }
+}
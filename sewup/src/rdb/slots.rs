@@ -0,0 +1,193 @@
+//! The slot map of table, which maps the id of record to the row in the storage of table, such
+//! that the rows of deleted records can be reclaimed while the ids handed to clients stay valid.
+//!
+//! The id of record is the position of the row before any row is reclaimed, so the map is only
+//! saved after `Table::vacuum` or the reuse of deleted rows, in the slots addressed by the hash of
+//! table signature. Each slot keeps the rows of 8 ids, which is the row + 1 in 4 bytes (BE) and
+//! 0 if the row is reclaimed, so only the slots of the changed ids are written on commit.
+//! ```compile_fail
+//! | address                | 0th ~ 3rd      | 4th ~ 7th      | ... | 28th ~ 31st        |
+//! |------------------------|----------------|----------------|-----|--------------------|
+//! | sha3("slots", sig, 0)  | -                                     | number of ids (BE) |
+//! | sha3("slots", sig, i)  | row of id 8i-7 | row of id 8i-6 | ... | row of id 8i       |
+//! ```
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+
+use crate::journal::{ChangeLog, Snapshot};
+use crate::rdb::db::TableSig;
+use crate::utils::sha3_256;
+
+/// The number of the rows of ids kept in a slot
+const ROWS_PER_SLOT: usize = 8;
+
+fn slot_map_addr(sig: &TableSig, idx: u32) -> [u8; 32] {
+    let mut input: Vec<u8> = "slots".as_bytes().into();
+    input.extend_from_slice(sig);
+    input.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&input)
+}
+
+/// The index of the slot keeping the row of id
+fn slot_of(id: usize) -> u32 {
+    ((id - 1) / ROWS_PER_SLOT + 1) as u32
+}
+
+/// Load the number of ids of the saved map, which is 0 if the map is never saved
+fn load_length(sig: &TableSig, snapshot: &Snapshot) -> usize {
+    let header = snapshot.load(&slot_map_addr(sig, 0));
+    u32::from_be_bytes(header[28..32].try_into().expect("load slot map size fail")) as usize
+}
+
+/// Clear the saved map of table, for example the table is dropped
+pub(crate) fn clear_slot_map(sig: &TableSig, snapshot: &Snapshot, change_log: &mut ChangeLog) {
+    let length = load_length(sig, snapshot);
+    if length == 0 {
+        return;
+    }
+    for i in 0..=slot_of(length) {
+        change_log.store(&slot_map_addr(sig, i), &[0u8; 32]);
+    }
+}
+
+/// The row of the record with the `id` is `rows[id - 1]`, `None` if the row is reclaimed
+pub(crate) struct SlotMap {
+    rows: Vec<Option<u32>>,
+    /// The map is the position of rows, which is not saved
+    identity: bool,
+    /// The number of ids of the saved map
+    saved_length: usize,
+    /// The slots of the changed ids, which are written on commit
+    dirty: BTreeSet<u32>,
+}
+
+impl Default for SlotMap {
+    fn default() -> Self {
+        Self {
+            rows: Vec::new(),
+            identity: true,
+            saved_length: 0,
+            dirty: BTreeSet::new(),
+        }
+    }
+}
+
+impl SlotMap {
+    /// Load the map of table, the map is the position of rows if it is never saved
+    pub(crate) fn load(sig: &TableSig, row_length: usize, snapshot: &Snapshot) -> Self {
+        let length = load_length(sig, snapshot);
+        if length == 0 {
+            return Self {
+                rows: (0..row_length as u32).map(Some).collect(),
+                ..Default::default()
+            };
+        }
+        let mut rows = Vec::with_capacity(length);
+        for i in 1..=slot_of(length) {
+            let buffer = snapshot.load(&slot_map_addr(sig, i));
+            for chunk in buffer.chunks(4) {
+                let row = u32::from_be_bytes(chunk.try_into().expect("load slot map fail"));
+                rows.push(row.checked_sub(1));
+            }
+        }
+        rows.truncate(length);
+        Self {
+            rows,
+            identity: false,
+            saved_length: length,
+            dirty: BTreeSet::new(),
+        }
+    }
+    /// The row of the record, `None` if the id is never used or the row is reclaimed
+    pub(crate) fn row(&self, id: usize) -> Option<usize> {
+        if id == 0 {
+            return None;
+        }
+        self.rows.get(id - 1).copied().flatten().map(|r| r as usize)
+    }
+
+    /// The number of ids ever used
+    pub(crate) fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The ids and the rows of records
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.rows
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| r.map(|r| (i + 1, r as usize)))
+    }
+
+    /// Assign a new id to the row
+    pub(crate) fn push(&mut self, row: usize) -> usize {
+        if self.identity && row != self.rows.len() {
+            self.identity = false;
+        }
+        self.rows.push(Some(row as u32));
+        self.mark_dirty(self.rows.len());
+        self.rows.len()
+    }
+
+    /// Move the records to the rows, and release the ids not moved
+    pub(crate) fn remap(&mut self, moved: impl Iterator<Item = (usize, usize)>) {
+        let mut rows = vec![None; self.rows.len()];
+        for (id, row) in moved {
+            rows[id - 1] = Some(row as u32);
+        }
+        for id in 1..=rows.len() {
+            if rows[id - 1] != self.rows[id - 1] {
+                self.mark_dirty(id);
+            }
+        }
+        self.rows = rows;
+        self.identity = false;
+    }
+
+    /// Release the id of the row, such that the row can be used by another record
+    pub(crate) fn release_row(&mut self, row: usize) {
+        if let Some(pos) = self.rows.iter().position(|r| *r == Some(row as u32)) {
+            self.rows[pos] = None;
+            self.identity = false;
+            self.mark_dirty(pos + 1);
+        }
+    }
+
+    fn mark_dirty(&mut self, id: usize) {
+        self.dirty.insert(slot_of(id));
+    }
+
+    /// Save the slots of the changed ids, all the slots are saved if the map is never saved
+    pub(crate) fn commit(&mut self, sig: &TableSig, change_log: &mut ChangeLog) {
+        if self.identity {
+            return;
+        }
+        let dirty = if self.saved_length == 0 {
+            (1..=self.rows.len())
+                .step_by(ROWS_PER_SLOT)
+                .map(slot_of)
+                .collect()
+        } else {
+            std::mem::take(&mut self.dirty)
+        };
+        for i in dirty.into_iter() {
+            let mut buffer = [0u8; 32];
+            let start = (i as usize - 1) * ROWS_PER_SLOT;
+            for (row, chunk) in self.rows[start..]
+                .iter()
+                .take(ROWS_PER_SLOT)
+                .zip(buffer.chunks_mut(4))
+            {
+                chunk.copy_from_slice(&row.map(|r| r + 1).unwrap_or(0).to_be_bytes());
+            }
+            change_log.store(&slot_map_addr(sig, i), &buffer);
+        }
+        if self.saved_length != self.rows.len() {
+            let mut header = [0u8; 32];
+            header[28..32].copy_from_slice(&(self.rows.len() as u32).to_be_bytes());
+            change_log.store(&slot_map_addr(sig, 0), &header);
+            self.saved_length = self.rows.len();
+        }
+        self.dirty.clear();
+    }
+}
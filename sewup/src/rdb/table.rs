@@ -1,33 +1,370 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
 use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
 
-use anyhow::Result;
-#[cfg(target_arch = "wasm32")]
-use ewasm_api::{storage_load, storage_store};
-
-#[cfg(target_arch = "wasm32")]
-use crate::rdb::db::Db;
-use crate::rdb::db::TableInfo;
+use crate::errors::StorageError;
+use crate::ordered::to_ordered_bytes;
+use crate::rdb::db::{Db, TableInfo, TableSig};
+use crate::rdb::heap::Heap;
+use crate::rdb::index::{ids_of_keys, update_entry, Index};
+use crate::rdb::query::{integer_of, Aggregate, Order, Predicate};
+use crate::rdb::relation::{
+    load_detached, load_relations, register_relation, store_detached, Relation,
+};
+use crate::rdb::schema::store_schema_version;
+use crate::rdb::slots::SlotMap;
 use crate::rdb::{
     errors::Error,
-    traits::{Record, HEADER_SIZE},
+    traits::{ForeignKey, OnDelete, Record, HEADER_SIZE, OVERFLOW_HEADER},
+    Feature,
 };
-#[cfg(target_arch = "wasm32")]
 use crate::types::Raw;
 use crate::types::Row;
-#[cfg(target_arch = "wasm32")]
-use crate::utils::storage_index_to_addr;
+use crate::{to_value, Value};
+use anyhow::Result;
+use serde::Serialize;
 
+/// The records of table are kept in rows, and the id of record is mapped to the row by the slot map,
+/// such that the rows of deleted records are reclaimed by `vacuum` without changing the ids.
 pub struct Table<T: Record> {
     pub(crate) info: TableInfo,
     pub(crate) data: Vec<Row>,
     pub(crate) phantom: PhantomData<T>,
+    /// The indexes of the fields listed in `Record::INDEXES`
+    pub(crate) indexes: Vec<Index>,
+    /// The binary of the records not fitting the size of row
+    pub(crate) heap: Heap,
+    /// The ids of the records added or updated, the foreign keys are checked on commit
+    pub(crate) changed: BTreeSet<usize>,
+    /// The ids of the records deleted, the children are checked on commit
+    pub(crate) deleted: BTreeSet<usize>,
+    /// The rows of records
+    pub(crate) slots: SlotMap,
+    /// The rows added or modified, which are written on commit
+    pub(crate) dirty_rows: BTreeSet<usize>,
+    /// The rows of deleted records are reused by the added records, `Feature::ReuseRows`
+    pub(crate) reuse_rows: bool,
+    /// The records detached by the deletion of parent records are loaded, which are rewritten
+    /// and no longer kept as detached on commit
+    pub(crate) detached: bool,
+    /// The block height of the snapshot the table loaded from, `None` for the latest storage
+    pub(crate) block_height: Option<u64>,
+}
+
+/// The child records of the other tables deleted or detached with the deleted records of table
+#[derive(Default)]
+struct Detachment {
+    /// The ids of the child records deleted and the indexed fields of the child tables
+    deleted: BTreeMap<TableSig, (BTreeSet<usize>, Vec<String>)>,
+    /// The ids of the child records whose foreign key is set to `None`
+    detached: BTreeMap<(TableSig, String), BTreeSet<usize>>,
 }
 
 impl<T: Record> Table<T> {
     /// Add a new record into table
+    /// The record is not added if the value of an unique field already exists.
+    /// The id of a deleted record is never reused, but the row of a deleted record is reused if
+    /// the db enables `Feature::ReuseRows`.
     pub fn add_record(&mut self, instance: T) -> Result<usize> {
-        self.data.push(instance.to_row(self.info.record_raw_size)?);
-        Ok(self.data.len())
+        let keys = self.check_unique(&instance, self.slots.len() + 1)?;
+        let (row, overflow) = self.to_row(&instance)?;
+        let id = match self.free_row() {
+            Some(r) => {
+                self.slots.release_row(r);
+                self.data[r] = row;
+                self.dirty_rows.insert(r);
+                self.slots.push(r)
+            }
+            None => {
+                self.data.push(row);
+                self.dirty_rows.insert(self.data.len() - 1);
+                self.slots.push(self.data.len() - 1)
+            }
+        };
+        if let Some(bin) = overflow {
+            self.heap.insert(id, bin);
+        }
+        for (index, key) in self.indexes.iter_mut().zip(keys) {
+            index.insert(key, id);
+        }
+        self.changed.insert(id);
+        Ok(id)
+    }
+
+    /// Encode the instance into the row of table, if the instance does not fit the size of row,
+    /// the row is a pointer and the binary is returned for the overflow heap
+    fn to_row(&self, instance: &T) -> Result<(Row, Option<Vec<u8>>)> {
+        if let Ok(row) = instance.to_row(self.info.record_raw_size) {
+            return Ok((row, None));
+        }
+        let bin = bincode::serialize(instance)?;
+        let mut pointer = vec![OVERFLOW_HEADER];
+        pointer.extend_from_slice(&(bin.len() as u32).to_be_bytes());
+        let mut raws = Row::from(pointer).into_raw_vec();
+        raws.resize(self.info.record_raw_size.max(1) as usize, Raw::default());
+        Ok((raws.into(), Some(bin)))
+    }
+
+    /// Returns true if the row is wiped, i.e. the record of the row is deleted
+    fn is_free(row: &Row) -> bool {
+        row.inner.first().map(|r| r.bytes[0] == 0).unwrap_or(true)
+    }
+
+    /// The first row of deleted records which can be reused
+    fn free_row(&self) -> Option<usize> {
+        if self.reuse_rows {
+            self.data.iter().position(Self::is_free)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the size of binary in the overflow heap if the row is a pointer
+    fn overflow_size(buffer: &[u8]) -> Option<usize> {
+        if buffer[0] == OVERFLOW_HEADER {
+            Some(
+                u32::from_be_bytes(buffer[1..5].try_into().expect("load overflow size fail"))
+                    as usize,
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Decode the record with the id, `None` if the record is deleted
+    fn record(&self, id: usize) -> Option<T> {
+        let mut row: Row = self.data.get(self.slots.row(id)?)?.clone();
+        row.make_buffer();
+        if Self::overflow_size(row.borrow()).is_some() {
+            self.heap
+                .get(id)
+                .map(|bin| bincode::deserialize(bin).expect("load a record"))
+        } else {
+            T::from_row(&row)
+        }
+    }
+
+    /// Returns the index keys of the instance, or the error if the value of an unique field is
+    /// used by a record other than `id`
+    fn check_unique(&self, instance: &T, id: usize) -> Result<Vec<Vec<u8>>> {
+        let mut keys = Vec::with_capacity(self.indexes.len());
+        for index in self.indexes.iter() {
+            let key = instance.field_key(index.field)?;
+            if index.unique && index.conflicts(&key, id) {
+                return Err(Error::UniqueViolation(index.field.into()).into());
+            }
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    /// Remove the index keys of the record, if the record is not deleted
+    fn unindex_record(&mut self, id: usize) -> Result<()> {
+        if self.indexes.is_empty() {
+            return Ok(());
+        }
+        if let Some(old) = self.record(id) {
+            for index in self.indexes.iter_mut() {
+                index.remove(old.field_key(index.field)?, id);
+            }
+        }
+        Ok(())
+    }
+
+    fn index(&self, field: &str) -> Option<&Index> {
+        self.indexes.iter().find(|i| i.field == field)
+    }
+
+    /// The ids of records with the value of indexed field in the range, in the order of the
+    /// field
+    pub fn index_ids<K: Serialize, R: RangeBounds<K>>(
+        &self,
+        field: &str,
+        range: R,
+    ) -> Result<Vec<usize>> {
+        let index = self
+            .index(field)
+            .ok_or_else(|| Error::FieldNotIndexed(field.into()))?;
+        let encode = |bound: Bound<&K>| -> Result<Bound<Vec<u8>>> {
+            Ok(match bound {
+                Bound::Included(k) => Bound::Included(to_ordered_bytes(k)?),
+                Bound::Excluded(k) => Bound::Excluded(to_ordered_bytes(k)?),
+                Bound::Unbounded => Bound::Unbounded,
+            })
+        };
+        Ok(index.ids((encode(range.start_bound())?, encode(range.end_bound())?)))
+    }
+
+    /// Find the records with the value of indexed field
+    pub fn find_by_index<K: Serialize>(&self, field: &str, value: &K) -> Result<Vec<(usize, T)>> {
+        self.index_ids::<K, _>(field, value..=value)?
+            .into_iter()
+            .map(|id| Ok((id, self.get_record(id)?)))
+            .collect()
+    }
+
+    /// Find the records with the value of indexed field in the range, in the order of the field
+    pub fn range_by_index<K: Serialize, R: RangeBounds<K>>(
+        &self,
+        field: &str,
+        range: R,
+    ) -> Result<Vec<(usize, T)>> {
+        self.index_ids(field, range)?
+            .into_iter()
+            .map(|id| Ok((id, self.get_record(id)?)))
+            .collect()
+    }
+
+    /// Query the records matching all the predicates, the records are sorted by the field of
+    /// `order_by` and then the id, and the page is selected by `offset` and `limit`.
+    /// The indexes of fields are used to look up the records if available.
+    pub fn query(
+        &self,
+        predicates: &[Predicate],
+        order_by: Option<&(String, Order)>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<(usize, T)>> {
+        let mut records = self.matched_records(predicates)?;
+        if let Some((field, order)) = order_by {
+            let mut keyed = Vec::with_capacity(records.len());
+            for (id, r) in records.into_iter() {
+                keyed.push((r.field_key(field)?, id, r));
+            }
+            keyed.sort_by(|a, b| match order {
+                Order::Asc => a.0.cmp(&b.0).then(a.1.cmp(&b.1)),
+                Order::Desc => b.0.cmp(&a.0).then(a.1.cmp(&b.1)),
+            });
+            records = keyed.into_iter().map(|(_, id, r)| (id, r)).collect();
+        }
+        let records = records.into_iter().skip(offset);
+        Ok(if let Some(limit) = limit {
+            records.take(limit).collect()
+        } else {
+            records.collect()
+        })
+    }
+
+    /// Count the records matching all the predicates
+    pub fn count(&self, predicates: &[Predicate]) -> Result<usize> {
+        Ok(self.matched_records(predicates)?.len())
+    }
+
+    /// Aggregate the records matching all the predicates, `Count` is the number of records
+    pub fn aggregate(
+        &self,
+        aggregate: &Aggregate,
+        predicates: &[Predicate],
+    ) -> Result<Option<i128>> {
+        let records = self.matched_records(predicates)?;
+        Self::aggregate_records(aggregate, records.iter().map(|(_, r)| r))
+    }
+
+    /// The sum of the integer field of the records matching all the predicates
+    pub fn sum(&self, field: &str, predicates: &[Predicate]) -> Result<i128> {
+        Ok(self
+            .aggregate(&Aggregate::Sum(field.into()), predicates)?
+            .unwrap_or_default())
+    }
+
+    /// The minimum of the integer field of the records matching all the predicates
+    pub fn min(&self, field: &str, predicates: &[Predicate]) -> Result<Option<i128>> {
+        self.aggregate(&Aggregate::Min(field.into()), predicates)
+    }
+
+    /// The maximum of the integer field of the records matching all the predicates
+    pub fn max(&self, field: &str, predicates: &[Predicate]) -> Result<Option<i128>> {
+        self.aggregate(&Aggregate::Max(field.into()), predicates)
+    }
+
+    /// Group the records matching all the predicates by the value of field, and aggregate the
+    /// records of each group, the groups are in the order of the value of field.
+    pub fn group_by(
+        &self,
+        field: &str,
+        aggregate: &Aggregate,
+        predicates: &[Predicate],
+    ) -> Result<Vec<(Value, Option<i128>)>> {
+        let mut groups: BTreeMap<Vec<u8>, (Value, Vec<T>)> = BTreeMap::new();
+        for (_, r) in self.matched_records(predicates)?.into_iter() {
+            let key = r.field_key(field)?;
+            if let Some((_, records)) = groups.get_mut(&key) {
+                records.push(r);
+            } else {
+                groups.insert(key, (field_value(&r, field)?, vec![r]));
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(_, (value, records))| {
+                Ok((value, Self::aggregate_records(aggregate, records.iter())?))
+            })
+            .collect()
+    }
+
+    fn aggregate_records<'a, I: Iterator<Item = &'a T>>(
+        aggregate: &Aggregate,
+        records: I,
+    ) -> Result<Option<i128>>
+    where
+        T: 'a,
+    {
+        if let Some(field) = aggregate.field() {
+            let mut values = Vec::new();
+            for r in records {
+                if let Some(i) = integer_of(field, field_value(r, field)?)? {
+                    values.push(i);
+                }
+            }
+            Ok(aggregate.apply(values.into_iter()))
+        } else {
+            Ok(Some(records.count() as i128))
+        }
+    }
+
+    /// The records matching all the predicates in the order of id
+    fn matched_records(&self, predicates: &[Predicate]) -> Result<Vec<(usize, T)>> {
+        let mut candidates: Option<Vec<usize>> = None;
+        for predicate in predicates.iter() {
+            if let (Some(index), Some(ranges)) = (
+                self.index(&predicate.field),
+                predicate.condition.index_ranges(),
+            ) {
+                let mut ids: Vec<usize> = ranges.into_iter().flat_map(|r| index.ids(r)).collect();
+                ids.sort_unstable();
+                ids.dedup();
+                candidates = Some(match candidates {
+                    Some(c) => c
+                        .into_iter()
+                        .filter(|id| ids.binary_search(id).is_ok())
+                        .collect(),
+                    None => ids,
+                });
+            }
+        }
+
+        let ids: Vec<usize> = match candidates {
+            Some(ids) => ids,
+            None => (1..=self.slots.len()).collect(),
+        };
+        let mut output = Vec::new();
+        for id in ids.into_iter() {
+            if let Some(r) = self.record(id) {
+                let mut all_match = true;
+                for predicate in predicates.iter() {
+                    if !predicate.condition.matches(&r.field_key(&predicate.field)?) {
+                        all_match = false;
+                        break;
+                    }
+                }
+                if all_match {
+                    output.push((id, r));
+                }
+            }
+        }
+        Ok(output)
     }
 
     /// Get a record with specific id
@@ -37,9 +374,7 @@ impl<T: Record> Table<T> {
         } else if self.data.len() == 0 {
             Err(Error::TableIsEmpty.into())
         } else {
-            let mut row: Row = self.data[id - 1].clone();
-            row.make_buffer();
-            if let Some(i) = T::from_row(&row) {
+            if let Some(i) = self.record(id) {
                 Ok(i)
             } else {
                 Err(Error::RecordDeleted.into())
@@ -50,10 +385,8 @@ impl<T: Record> Table<T> {
     /// Get all records
     pub fn all_records(&self) -> Result<Vec<T>> {
         let mut output: Vec<T> = Vec::new();
-        for r in self.data.iter() {
-            let mut buffer_row = r.clone();
-            buffer_row.make_buffer();
-            if let Some(i) = T::from_row(&buffer_row) {
+        for id in 1..=self.slots.len() {
+            if let Some(i) = self.record(id) {
                 output.push(i);
             }
         }
@@ -64,12 +397,10 @@ impl<T: Record> Table<T> {
     /// Filter the records
     pub fn filter_records(&self, filter: &dyn Fn(&T) -> bool) -> Result<Vec<(usize, T)>> {
         let mut output: Vec<(usize, T)> = Vec::new();
-        for (idx, r) in self.data.iter().enumerate() {
-            let mut buffer_row = r.clone();
-            buffer_row.make_buffer();
-            if let Some(i) = T::from_row(&buffer_row) {
+        for id in 1..=self.slots.len() {
+            if let Some(i) = self.record(id) {
                 if filter(&i) {
-                    output.push((idx + 1, i));
+                    output.push((id, i));
                 }
             }
         }
@@ -84,30 +415,306 @@ impl<T: Record> Table<T> {
             Err(Error::TableIsEmpty.into())
         } else {
             if let Some(instance) = instance {
-                self.data[id - 1] = instance.to_row(self.info.record_raw_size)?;
+                let row_idx = self.slots.row(id).ok_or(Error::RecordDeleted)?;
+                let keys = self.check_unique(&instance, id)?;
+                let (row, overflow) = self.to_row(&instance)?;
+                self.unindex_record(id)?;
+                self.data[row_idx] = row;
+                self.dirty_rows.insert(row_idx);
+                if let Some(bin) = overflow {
+                    self.heap.insert(id, bin);
+                } else {
+                    self.heap.remove(id);
+                }
+                for (index, key) in self.indexes.iter_mut().zip(keys) {
+                    index.insert(key, id);
+                }
+                self.changed.insert(id);
+                self.deleted.remove(&id);
             } else {
-                self.data[id - 1].wipe_header(HEADER_SIZE as usize)
+                let row_idx = self.slots.row(id).ok_or(Error::RecordDeleted)?;
+                self.unindex_record(id)?;
+                self.heap.remove(id);
+                self.data[row_idx].wipe_header(HEADER_SIZE as usize);
+                self.dirty_rows.insert(row_idx);
+                self.changed.remove(&id);
+                self.deleted.insert(id);
             }
             Ok(())
         };
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub(crate) fn load_data(self) -> Result<Self> {
-        Ok(self)
+    /// Returns true if the record with the id exists in the parent table
+    fn parent_exists(&self, db: &Db, parent: &TableSig, id: usize) -> bool {
+        if *parent == self.info.sig {
+            self.record(id).is_some()
+        } else {
+            db.record_exists(parent, id)
+        }
+    }
+
+    /// Check the parent records of the added or updated records exist
+    fn check_foreign_keys(&self, db: &Db) -> Result<()> {
+        for fk in T::foreign_keys().iter() {
+            let parent = db.table_sig((fk.parent)());
+            for id in self.changed.iter() {
+                if let Some(parent_id) = self.record(*id).and_then(|r| (fk.id)(&r)) {
+                    let exists = parent
+                        .map(|parent| self.parent_exists(db, &parent, parent_id))
+                        .unwrap_or_default();
+                    if !exists {
+                        return Err(Error::ForeignKeyViolation(fk.field.into()).into());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The keys of the foreign keys referencing the records, which may be `usize` or
+    /// `Option<usize>`
+    fn referencing_keys(ids: &BTreeSet<usize>) -> Result<Vec<Vec<u8>>> {
+        let mut keys = Vec::with_capacity(ids.len() * 2);
+        for id in ids.iter() {
+            keys.push(to_ordered_bytes(id)?);
+            keys.push(to_ordered_bytes(&Some(*id))?);
+        }
+        Ok(keys)
+    }
+
+    /// Delete or detach the records referencing the deleted records in the same table by the
+    /// `on_delete` of foreign keys, and the records deleted are also dereferenced
+    fn detach_own_children(&mut self, db: &Db) -> Result<()> {
+        let foreign_keys: Vec<ForeignKey<T>> = T::foreign_keys()
+            .into_iter()
+            .filter(|fk| {
+                fk.on_delete != OnDelete::Restrict
+                    && db.table_sig((fk.parent)()) == Some(self.info.sig)
+            })
+            .collect();
+        let mut pending: Vec<usize> = if foreign_keys.is_empty() {
+            Vec::new()
+        } else {
+            self.deleted.iter().copied().collect()
+        };
+        while let Some(parent_id) = pending.pop() {
+            let keys = Self::referencing_keys(&[parent_id].into_iter().collect())?;
+            for fk in foreign_keys.iter() {
+                let ids: Vec<usize> = match self.index(fk.field) {
+                    Some(index) => keys
+                        .iter()
+                        .flat_map(|k| index.ids(k.clone()..=k.clone()))
+                        .collect(),
+                    None => continue,
+                };
+                for id in ids.into_iter() {
+                    let mut record = match self.record(id) {
+                        Some(r) if (fk.id)(&r) == Some(parent_id) => r,
+                        _ => continue,
+                    };
+                    match (fk.on_delete, fk.set_none) {
+                        (OnDelete::SetNone, Some(set_none)) => {
+                            set_none(&mut record);
+                            self.update_record(id, Some(record))?;
+                        }
+                        _ => {
+                            self.update_record(id, None)?;
+                            pending.push(id);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the child records of the other tables referencing the deleted records by the
+    /// relations, the children of the deleted child records are also found.
+    /// Fails if a deleted record is referenced by the child records with `OnDelete::Restrict`.
+    fn detach_children(&self, db: &Db) -> Result<Detachment> {
+        let mut detachment = Detachment::default();
+        let mut pending = vec![(self.info.sig, self.deleted.clone())];
+        while let Some((parent, ids)) = pending.pop() {
+            if ids.is_empty() {
+                continue;
+            }
+            let keys = Self::referencing_keys(&ids)?;
+            for relation in load_relations(&parent, &db.snapshot) {
+                if relation.child == self.info.sig {
+                    // The records of this table referencing the deleted records are deleted or
+                    // detached before
+                    let referenced = parent == self.info.sig
+                        && relation.on_delete == OnDelete::Restrict
+                        && self
+                            .index(&relation.field)
+                            .map(|index| {
+                                keys.iter()
+                                    .any(|k| !index.ids(k.clone()..=k.clone()).is_empty())
+                            })
+                            .unwrap_or_default();
+                    if referenced {
+                        return Err(Error::ForeignKeyRestricted(relation.field).into());
+                    }
+                    continue;
+                }
+                if !db.table_info.iter().any(|info| info.sig == relation.child) {
+                    continue;
+                }
+                let mut children: BTreeSet<usize> =
+                    ids_of_keys(&relation.child, &relation.field, &keys, &db.snapshot)
+                        .into_iter()
+                        .collect();
+                if let Some((deleted, _)) = detachment.deleted.get(&relation.child) {
+                    children.retain(|id| !deleted.contains(id));
+                }
+                if children.is_empty() {
+                    continue;
+                }
+                match relation.on_delete {
+                    OnDelete::Restrict => {
+                        return Err(Error::ForeignKeyRestricted(relation.field).into())
+                    }
+                    OnDelete::Cascade => {
+                        detachment
+                            .deleted
+                            .entry(relation.child)
+                            .or_insert_with(|| (BTreeSet::new(), relation.indexes.clone()))
+                            .0
+                            .extend(children.iter().copied());
+                        pending.push((relation.child, children));
+                    }
+                    OnDelete::SetNone => detachment
+                        .detached
+                        .entry((relation.child, relation.field))
+                        .or_default()
+                        .extend(children),
+                }
+            }
+        }
+        Ok(detachment)
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub(crate) fn load_data(self) -> Result<Self> {
+    /// Set the foreign key of the detached record to `None`, which is saved when the table is
+    /// committed
+    fn reattach(&mut self, field: &str, id: usize) -> Result<()> {
+        let set_none = T::foreign_keys()
+            .into_iter()
+            .find(|fk| fk.field == field)
+            .and_then(|fk| fk.set_none);
+        if let (Some(set_none), Some(mut record)) = (set_none, self.record(id)) {
+            set_none(&mut record);
+            self.update_record(id, Some(record))?;
+        }
+        Ok(())
+    }
+
+    /// Reclaim the rows of deleted records and returns the number of reclaimed rows
+    /// The ids of the records are not changed, and the storage of table is shrunk when the table
+    /// is committed.
+    pub fn vacuum(&mut self) -> usize {
+        let live: Vec<(usize, usize)> = self
+            .slots
+            .iter()
+            .filter(|(_, row)| !Self::is_free(&self.data[*row]))
+            .collect();
+        let reclaimed = self.data.len() - live.len();
+        if reclaimed == 0 {
+            return 0;
+        }
+        let mut data = Vec::with_capacity(live.len());
+        let mut moved = Vec::with_capacity(live.len());
+        for (id, row) in live.into_iter() {
+            moved.push((id, data.len()));
+            data.push(self.data[row].clone());
+        }
+        self.data = data;
+        self.dirty_rows = (0..self.data.len()).collect();
+        self.slots.remap(moved.into_iter());
+        reclaimed
+    }
+
+    /// Rewrite the records into the schema of `U` with the size of row, the ids of records are
+    /// kept and the indexes of `U` are rebuilt
+    pub(crate) fn migrate<U: Record>(
+        mut self,
+        record_raw_size: u32,
+        convert: impl Fn(T) -> U,
+    ) -> Result<Table<U>> {
+        let mut info = self.info.clone();
+        info.record_raw_size = record_raw_size;
+        let empty_row: Row = vec![Raw::default(); record_raw_size as usize].into();
+        let mut table = Table::<U> {
+            info,
+            data: vec![empty_row; self.data.len()],
+            phantom: PhantomData,
+            indexes: Vec::with_capacity(U::INDEXES.len()),
+            heap: Heap::default(),
+            changed: BTreeSet::new(),
+            deleted: BTreeSet::new(),
+            slots: SlotMap::default(),
+            reuse_rows: self.reuse_rows,
+            dirty_rows: (0..self.data.len()).collect(),
+            detached: self.detached,
+            block_height: self.block_height,
+        };
+
+        let mut records = Vec::new();
+        for (id, row) in self.slots.iter() {
+            if let Some(record) = self.record(id) {
+                records.push((id, row, convert(record)));
+            }
+        }
+        // The binaries of the old records are replaced or cleared on commit
+        table.heap = std::mem::take(&mut self.heap);
+        table.slots = std::mem::take(&mut self.slots);
+        for id in 1..=table.slots.len() {
+            table.heap.remove(id);
+        }
+
+        let mut keys: Vec<Vec<(Vec<u8>, usize)>> = vec![Vec::new(); U::INDEXES.len()];
+        for (id, row, record) in records.into_iter() {
+            for ((field, _), keys) in U::INDEXES.iter().zip(keys.iter_mut()) {
+                keys.push((record.field_key(field)?, id));
+            }
+            let (data, overflow) = table.to_row(&record)?;
+            table.data[row] = data;
+            if let Some(bin) = overflow {
+                table.heap.insert(id, bin);
+            }
+            table.changed.insert(id);
+        }
+
+        for ((field, unique), keys) in U::INDEXES.iter().zip(keys) {
+            let mut index = Index::build(field, *unique, std::iter::empty());
+            if let Some(saved) = self.indexes.iter_mut().position(|i| i.field == *field) {
+                // Keep the loaded index, such that the slots no longer used are cleared
+                index = self.indexes.swap_remove(saved);
+                index.unique = *unique;
+            }
+            index.rebuild(keys.iter().cloned());
+            if *unique && keys.iter().any(|(key, id)| index.conflicts(key, *id)) {
+                return Err(Error::UniqueViolation(field.to_string()).into());
+            }
+            table.indexes.push(index);
+        }
+
+        Ok(table)
+    }
+
+    /// Load the data of table from the snapshot of the db
+    /// The index never saved is built from the records, for example the index is added to an
+    /// existing table.
+    /// The foreign keys of the records detached by the deletion of parent records are set to
+    /// `None`, and the records are rewritten when the table is committed.
+    pub(crate) fn load_data(self, db: &Db) -> Result<Self> {
+        let snapshot = &db.snapshot;
         let Self { info, phantom, .. } = self;
-        let mut db = Db::load(None)?;
-        let mut addr: [u8; 32] = [0; 32];
+        let block_height = snapshot.block_height();
         let mut data: Vec<Row> = Vec::new();
         let mut buffer: Vec<Raw> = Vec::new();
 
         for storage_idx in info.clone().range {
-            storage_index_to_addr(storage_idx as usize, &mut addr);
-            let raw: Raw = (&storage_load(&addr.into()).bytes).into();
+            let raw: Raw = (&snapshot.load(&db.table_addr(&info.sig, storage_idx))).into();
             buffer.push(raw);
             if buffer.len() % info.record_raw_size as usize == 0 {
                 data.push(buffer.into());
@@ -115,42 +722,159 @@ impl<T: Record> Table<T> {
             }
         }
 
-        Ok(Table::<T> {
+        let slots = SlotMap::load(&info.sig, data.len(), snapshot);
+        let mut heap = Heap::default();
+        for (id, idx) in slots.iter() {
+            let mut row = match data.get(idx) {
+                Some(row) => row.clone(),
+                None => continue,
+            };
+            row.make_buffer();
+            if let Some(size) = Self::overflow_size(row.borrow()) {
+                heap.load(&info.sig, id, size, snapshot);
+            }
+        }
+
+        let mut table = Table::<T> {
             info,
             data,
             phantom,
-        })
-    }
+            indexes: Vec::with_capacity(T::INDEXES.len()),
+            heap,
+            changed: BTreeSet::new(),
+            deleted: BTreeSet::new(),
+            slots,
+            reuse_rows: db.features().contains(&Feature::ReuseRows),
+            dirty_rows: BTreeSet::new(),
+            detached: false,
+            block_height,
+        };
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn commit(&self) -> Result<u32> {
-        Ok(0)
+        for (field, unique) in T::INDEXES.iter() {
+            let index = match Index::load(&table.info.sig, field, *unique, snapshot) {
+                Some(index) => index,
+                None => {
+                    let mut keys = Vec::new();
+                    for id in 1..=table.slots.len() {
+                        if let Some(record) = table.record(id) {
+                            keys.push((record.field_key(field)?, id));
+                        }
+                    }
+                    Index::build(field, *unique, keys.into_iter())
+                }
+            };
+            table.indexes.push(index);
+        }
+        for (field, id) in load_detached(&table.info.sig, snapshot).into_iter() {
+            table.reattach(&field, id)?;
+            table.detached = true;
+        }
+
+        Ok(table)
     }
 
     /// Dump the data of table on chain, and also update all the table info
-    #[cfg(target_arch = "wasm32")]
+    /// The commit fails if the parent record of a foreign key does not exist, or a deleted record
+    /// is referenced by the child records with `OnDelete::Restrict`.
+    /// The child records referencing the deleted records are deleted or detached by the
+    /// `on_delete` of the relations registered by the child tables, and the children of the
+    /// deleted child records are also deleted or detached. The child records are found by the
+    /// index of foreign key, which is added by the `Table` derive.
+    /// The table loaded from a past block can not be committed.
     pub fn commit(mut self) -> Result<u32> {
-        // Currently, wasm runs in single thread mode, so it is ok to do this.
+        if let Some(block_height) = self.block_height {
+            return Err(StorageError::CommitPastVersion(block_height).into());
+        }
+        // Currently, wasm runs in single thread mode, and the memory storage on host is kept
+        // per thread, so it is ok to do this.
         // If multiple treading happened, use Arc on DB and refactor this
         let raw_length = self.data.iter().fold(0u32, |sum, r| sum + r.len() as u32);
         let mut db = Db::load(None)?;
-        let mut addr: [u8; 32] = [0; 32];
+        if let Some(info) = db.table_info.iter_mut().find(|i| i.sig == self.info.sig) {
+            info.record_raw_size = self.info.record_raw_size;
+        }
+        self.check_foreign_keys(&db)?;
+        self.detach_own_children(&db)?;
+        let detachment = self.detach_children(&db)?;
+        let mut change_log = db.change_log();
 
-        let mut raw_list: Vec<Raw> = Vec::new();
-        for row in self.data.drain(..) {
-            raw_list.append(&mut row.into_raw_vec());
+        for fk in T::foreign_keys().iter() {
+            let relation = Relation {
+                child: self.info.sig,
+                field: fk.field.into(),
+                on_delete: fk.on_delete,
+                indexes: T::INDEXES.iter().map(|(f, _)| f.to_string()).collect(),
+            };
+            if let Some(parent) = db.table_sig((fk.parent)()) {
+                register_relation(&parent, relation, &db.snapshot, &mut change_log);
+            }
+        }
+
+        let none_key = to_ordered_bytes(&None::<usize>)?;
+        for ((child, field), ids) in detachment.detached.into_iter() {
+            for id in ids.iter() {
+                update_entry(
+                    &child,
+                    &field,
+                    *id,
+                    Some(&none_key),
+                    &db.snapshot,
+                    &mut change_log,
+                );
+            }
+            let mut detached = load_detached(&child, &db.snapshot);
+            detached.extend(ids.into_iter().map(|id| (field.clone(), id)));
+            store_detached(&child, &detached, &db.snapshot, &mut change_log);
+        }
+        for (child, (ids, fields)) in detachment.deleted.iter() {
+            db.wipe_records(child, ids, fields, &mut change_log);
+        }
+        if self.detached {
+            store_detached(&self.info.sig, &[], &db.snapshot, &mut change_log);
         }
 
-        for (idx, storage_idx) in db
-            .alloc_table_storage(self.info.sig, raw_length)?
-            .enumerate()
-        {
-            storage_index_to_addr(storage_idx as usize, &mut addr);
-            storage_store(&addr.into(), &raw_list[idx].to_bytes32().into());
+        // Only the changed rows are written, and the slots no longer used are cleared
+        let previous = db.resize_table_storage(self.info.sig, raw_length)?;
+        let mut storage_idx = 0u32;
+        for (idx, row) in self.data.drain(..).enumerate() {
+            let raws = row.into_raw_vec();
+            if self.dirty_rows.contains(&idx) {
+                for (i, raw) in raws.iter().enumerate() {
+                    let addr = db.table_addr(&self.info.sig, storage_idx + i as u32);
+                    change_log.store(&addr, &raw.to_bytes32());
+                }
+            }
+            storage_idx += raws.len() as u32;
+        }
+        for idx in raw_length..previous.end {
+            change_log.store(&db.table_addr(&self.info.sig, idx), &[0u8; 32]);
         }
+        self.dirty_rows.clear();
+        for index in self.indexes.iter_mut() {
+            index.commit(&self.info.sig, &mut change_log);
+        }
+        self.heap.commit(&self.info.sig, &mut change_log);
+        self.slots.commit(&self.info.sig, &mut change_log);
+        store_schema_version(
+            &self.info.sig,
+            T::SCHEMA_VERSION,
+            &db.snapshot,
+            &mut change_log,
+        );
 
-        db.commit()?;
+        db.commit_header(&mut change_log)?;
+        change_log.commit();
 
-        Ok(raw_list.len() as u32)
+        Ok(raw_length)
+    }
+}
+
+/// The value of the field of record, or `UnknownField`
+fn field_value<T: Record>(record: &T, field: &str) -> Result<Value> {
+    if let Ok(Value::Map(mut map)) = to_value(record) {
+        if let Some(value) = map.remove(&Value::String(field.into())) {
+            return Ok(value);
+        }
     }
+    Err(Error::UnknownField(field.into()).into())
 }
@@ -0,0 +1,314 @@
+use crate::errors::StorageError;
+use crate::ordered::to_ordered_bytes;
+use crate::rdb::errors::Error;
+use crate::rdb::tests::{init_db, Person, Post};
+use crate::rdb::traits::{ForeignKey, OnDelete, Record};
+use crate::rdb::{Db, Feature};
+use crate::storage::with_backend;
+use crate::{Deserialize, Serialize};
+
+use anyhow::Result;
+
+#[test]
+fn test_commit_and_load_tables_in_memory() {
+    init_db(|db| {
+        db.create_table::<Person>()?;
+        db.create_table::<Post>()
+    });
+
+    let mut posts = Db::load(None).unwrap().table::<Post>().unwrap();
+    posts
+        .add_record(Post {
+            serial: 1,
+            title: "title".into(),
+        })
+        .unwrap();
+    posts.commit().unwrap();
+
+    let mut people = Db::load(None).unwrap().table::<Person>().unwrap();
+    for age in 1..4 {
+        people.add_record(Person { age, trusted: true }).unwrap();
+    }
+    people.commit().unwrap();
+
+    let db = Db::load(None).unwrap();
+    assert_eq!(db.table_length(), 2);
+    let people = Db::load(None).unwrap().table::<Person>().unwrap();
+    assert_eq!(people.get_record(2).unwrap().age, 2);
+    assert_eq!(people.all_records().unwrap().len(), 3);
+    let posts = db.table::<Post>().unwrap();
+    assert_eq!(posts.get_record(1).unwrap().title, "title");
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Article {
+    title: String,
+    tags: Vec<Vec<u8>>,
+    votes: u32,
+}
+impl Record for Article {}
+
+#[test]
+fn test_variable_size_records_in_memory() {
+    init_db(|db| db.create_table::<Article>());
+
+    let long = Article {
+        title: "A long title which can not be saved in a single row".repeat(3),
+        tags: vec![vec![1u8; 40], vec![], vec![2u8; 3]],
+        votes: 7,
+    };
+    let mut articles = Db::load(None).unwrap().table::<Article>().unwrap();
+    articles.add_record(long.clone()).unwrap();
+    articles
+        .add_record(Article {
+            title: "Another title longer than a row".into(),
+            ..Default::default()
+        })
+        .unwrap();
+    articles.commit().unwrap();
+    let slots = with_backend(|memory| memory.slots.len());
+
+    let mut articles = Db::load(None).unwrap().table::<Article>().unwrap();
+    assert_eq!(articles.get_record(1).unwrap(), long);
+    assert_eq!(
+        articles.get_record(2).unwrap().title,
+        "Another title longer than a row"
+    );
+
+    articles
+        .update_record(
+            1,
+            Some(Article {
+                title: "shorter".into(),
+                ..long.clone()
+            }),
+        )
+        .unwrap();
+    articles.update_record(2, None).unwrap();
+    articles.commit().unwrap();
+    assert!(with_backend(|memory| memory.slots.len()) < slots);
+
+    let articles = Db::load(None).unwrap().table::<Article>().unwrap();
+    assert_eq!(articles.get_record(1).unwrap().title, "shorter");
+    assert_eq!(articles.get_record(1).unwrap().tags, long.tags);
+    assert!(articles.get_record(2).is_err());
+    assert_eq!(articles.all_records().unwrap().len(), 1);
+
+    let short = Article {
+        title: "short".into(),
+        ..Default::default()
+    };
+    let mut articles = Db::load(None).unwrap().table::<Article>().unwrap();
+    articles.add_record(Article::default()).unwrap();
+    articles.add_record(short.clone()).unwrap();
+    articles.update_record(1, Some(short.clone())).unwrap();
+    articles.commit().unwrap();
+
+    let articles = Db::load(None).unwrap().table::<Article>().unwrap();
+    assert_eq!(articles.get_record(1).unwrap(), short);
+    assert_eq!(articles.get_record(3).unwrap(), Article::default());
+    assert_eq!(articles.get_record(4).unwrap(), short);
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Item {
+    serial: u32,
+    owner_id: usize,
+    title: String,
+}
+impl Record for Item {
+    const INDEXES: &'static [(&'static str, bool)] = &[("serial", true)];
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        match field {
+            "serial" => Ok(to_ordered_bytes(&self.serial)?),
+            _ => Err(Error::UnknownField(field.into()).into()),
+        }
+    }
+
+    fn foreign_keys() -> Vec<ForeignKey<Self>> {
+        vec![ForeignKey {
+            field: "owner_id",
+            parent: Person::table_name,
+            on_delete: OnDelete::Restrict,
+            id: |r| Some(r.owner_id),
+            set_none: None,
+        }]
+    }
+}
+
+#[test]
+fn test_drop_and_recreate_table_in_memory() {
+    init_db(|db| {
+        db.create_table::<Person>()?;
+        db.create_table::<Item>()
+    });
+    let mut owners = Db::load(None).unwrap().table::<Person>().unwrap();
+    owners.add_record(Person::default()).unwrap();
+    owners.commit().unwrap();
+    let slots = with_backend(|memory| memory.slots.len());
+
+    let mut items = Db::load(None).unwrap().table::<Item>().unwrap();
+    for serial in 1..4 {
+        items
+            .add_record(Item {
+                serial,
+                owner_id: 1,
+                title: "A long title which can not be saved in a single row".repeat(2),
+            })
+            .unwrap();
+    }
+    items.update_record(2, None).unwrap();
+    items.vacuum();
+    items.commit().unwrap();
+
+    let mut db = Db::load(None).unwrap();
+    db.drop_table::<Item>();
+    db.commit().unwrap();
+    assert!(Db::load(None).unwrap().table_info::<Item>().is_none());
+    assert_eq!(with_backend(|memory| memory.slots.len()), slots);
+
+    // The owner is no longer restricted by the items dropped
+    let mut owners = Db::load(None).unwrap().table::<Person>().unwrap();
+    owners.update_record(1, None).unwrap();
+    owners.add_record(Person::default()).unwrap();
+    owners.commit().unwrap();
+
+    let mut db = Db::load(None).unwrap();
+    db.create_table::<Item>().unwrap();
+    db.commit().unwrap();
+    let mut items = Db::load(None).unwrap().table::<Item>().unwrap();
+    assert!(items.all_records().unwrap().is_empty());
+    assert_eq!(items.find_by_index("serial", &1u32).unwrap(), vec![]);
+    let item = Item {
+        serial: 1,
+        owner_id: 2,
+        title: "short".into(),
+    };
+    assert_eq!(items.add_record(item.clone()).unwrap(), 1);
+    items.commit().unwrap();
+
+    let items = Db::load(None).unwrap().table::<Item>().unwrap();
+    assert_eq!(items.get_record(1).unwrap(), item);
+    assert_eq!(
+        items.find_by_index("serial", &1u32).unwrap(),
+        vec![(1, item)]
+    );
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Account {
+    balance: u64,
+}
+impl Record for Account {}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Transfer {
+    from: usize,
+    amount: u64,
+}
+impl Record for Transfer {}
+
+#[test]
+fn test_transaction_in_memory() {
+    init_db(|db| {
+        db.create_table::<Account>()?;
+        db.create_table::<Transfer>()
+    });
+    let mut accounts = Db::load(None).unwrap().table::<Account>().unwrap();
+    accounts.add_record(Account { balance: 100 }).unwrap();
+    accounts.commit().unwrap();
+
+    let transfer = |amount: u64| {
+        Db::transaction(|tx| {
+            let mut transfers = tx.table::<Transfer>()?;
+            let id = transfers.add_record(Transfer { from: 1, amount })?;
+            tx.commit(transfers)?;
+            let mut accounts = tx.table::<Account>()?;
+            let mut account = accounts.get_record(1)?;
+            account.balance = account
+                .balance
+                .checked_sub(amount)
+                .ok_or_else(|| anyhow::anyhow!("insufficient balance"))?;
+            accounts.update_record(1, Some(account))?;
+            tx.commit(accounts)?;
+            Ok(id)
+        })
+    };
+
+    let before = with_backend(|memory| memory.slots.clone());
+    assert!(transfer(200).is_err());
+    assert_eq!(with_backend(|memory| memory.slots.clone()), before);
+
+    let staged = Db::transaction(|tx| {
+        let mut accounts = tx.table::<Account>()?;
+        accounts.add_record(Account { balance: 1 })?;
+        tx.commit(accounts)?;
+        assert_eq!(tx.table::<Account>()?.all_records()?.len(), 2);
+        Ok(with_backend(|memory| memory.slots.clone()))
+    })
+    .unwrap();
+    assert_eq!(staged, before);
+    assert_ne!(with_backend(|memory| memory.slots.clone()), before);
+
+    assert_eq!(transfer(30).unwrap(), 1);
+    let db = Db::load(None).unwrap();
+    let transfers = db.table::<Transfer>().unwrap();
+    assert_eq!(
+        transfers.get_record(1).unwrap(),
+        Transfer {
+            from: 1,
+            amount: 30
+        }
+    );
+    let accounts = Db::load(None).unwrap().table::<Account>().unwrap();
+    assert_eq!(accounts.get_record(1).unwrap(), Account { balance: 70 });
+    assert_eq!(accounts.get_record(2).unwrap(), Account { balance: 1 });
+}
+
+#[test]
+fn test_commit_table_loaded_at_past_block_in_memory() {
+    init_db(|db| {
+        db.enable_feature(Feature::Versioning);
+        db.create_table::<Person>()
+    });
+    let person = |age: u8| Person { age, trusted: true };
+
+    with_backend(|memory| memory.block_number = 1);
+    let mut people = Db::load(None).unwrap().table::<Person>().unwrap();
+    for age in 1..=3 {
+        people.add_record(person(age)).unwrap();
+    }
+    people.commit().unwrap();
+    with_backend(|memory| memory.block_number = 5);
+    let mut people = Db::load(None).unwrap().table::<Person>().unwrap();
+    for id in 1..=3 {
+        people
+            .update_record(id, Some(person(id as u8 * 10)))
+            .unwrap();
+    }
+    people.commit().unwrap();
+
+    // The table loaded from a past block is read only, such that the latest rows are not mixed
+    // with the rows of the past block
+    let mut people = Db::load(Some(3)).unwrap().table::<Person>().unwrap();
+    assert_eq!(people.get_record(2).unwrap(), person(2));
+    people.update_record(2, Some(person(99))).unwrap();
+    assert_eq!(
+        people
+            .commit()
+            .unwrap_err()
+            .downcast::<StorageError>()
+            .unwrap(),
+        StorageError::CommitPastVersion(3)
+    );
+    assert!(Db::load(Some(3)).unwrap().commit().is_err());
+
+    let people = Db::load(None).unwrap().table::<Person>().unwrap();
+    for id in 1..=3 {
+        assert_eq!(people.get_record(id).unwrap(), person(id as u8 * 10));
+    }
+    let people = Db::load(Some(0)).unwrap().table::<Person>().unwrap();
+    assert!(people.all_records().unwrap().is_empty());
+}
@@ -0,0 +1,85 @@
+use crate::rdb::errors::Error;
+use crate::rdb::tests::{init_db, Member};
+use crate::rdb::Db;
+use crate::storage::with_backend;
+
+fn member(serial: u32, age: u8) -> Member {
+    Member {
+        serial,
+        age,
+        active: true,
+    }
+}
+
+#[test]
+fn test_indexes_of_table_in_memory() {
+    init_db(|db| db.create_table::<Member>());
+
+    let mut members = Db::load(None).unwrap().table::<Member>().unwrap();
+    for (serial, age) in [(300u32, 20u8), (5, 9), (42, 20), (7, 65)].iter() {
+        members.add_record(member(*serial, *age)).unwrap();
+    }
+    let err = members.add_record(member(5, 1)).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Error>(),
+        Some(&Error::UniqueViolation("serial".into()))
+    );
+    members.commit().unwrap();
+
+    let mut members = Db::load(None).unwrap().table::<Member>().unwrap();
+    assert_eq!(
+        members.index_ids("serial", 6u32..=300).unwrap(),
+        vec![4, 3, 1]
+    );
+    assert_eq!(members.index_ids("age", 20u8..=20).unwrap(), vec![1, 3]);
+    assert_eq!(members.find_by_index("serial", &42u32).unwrap()[0].0, 3);
+    assert!(members.index_ids("missing", 0u8..).is_err());
+
+    members.update_record(1, Some(member(300, 21))).unwrap();
+    assert!(members.update_record(3, Some(member(7, 20))).is_err());
+    members.update_record(2, None).unwrap();
+    members.commit().unwrap();
+
+    let members = Db::load(None).unwrap().table::<Member>().unwrap();
+    let adults: Vec<u32> = members
+        .range_by_index("age", 18u8..)
+        .unwrap()
+        .into_iter()
+        .map(|(_, m)| m.serial)
+        .collect();
+    assert_eq!(adults, vec![42, 300, 7]);
+    assert!(members.find_by_index("serial", &5u32).unwrap().is_empty());
+}
+
+#[test]
+fn test_index_writes_of_added_record_in_memory() {
+    init_db(|db| db.create_table::<Member>());
+
+    let mut members = Db::load(None).unwrap().table::<Member>().unwrap();
+    for serial in 1000..1040u32 {
+        members.add_record(member(serial, 30)).unwrap();
+    }
+    members.commit().unwrap();
+
+    // Only the row, the index entries and the headers of table and indexes are written, which
+    // does not grow with the size of table
+    let before = with_backend(|memory| memory.slots.clone());
+    let mut members = Db::load(None).unwrap().table::<Member>().unwrap();
+    members.add_record(member(1, 1)).unwrap();
+    members.commit().unwrap();
+    let written = with_backend(|memory| {
+        memory
+            .slots
+            .iter()
+            .filter(|(k, v)| before.get(*k) != Some(*v))
+            .count()
+    });
+    assert_eq!(written, 6);
+
+    let members = Db::load(None).unwrap().table::<Member>().unwrap();
+    assert_eq!(
+        members.find_by_index("serial", &1u32).unwrap(),
+        vec![(41, member(1, 1))]
+    );
+    assert_eq!(members.index_ids("age", 30u8..=30).unwrap().len(), 40);
+}
@@ -0,0 +1,72 @@
+//! The tests of rdb on the memory storage, which are grouped by the features of db, and the
+//! records shared by the tests are defined here.
+use crate::ordered::to_ordered_bytes;
+use crate::rdb::errors::Error;
+use crate::rdb::traits::Record;
+use crate::rdb::Db;
+use crate::storage::{with_backend, MemoryStorage};
+use crate::{Deserialize, Serialize};
+
+use anyhow::Result;
+
+mod db;
+mod index;
+mod query;
+mod relation;
+mod schema;
+mod slots;
+
+/// Reset the memory storage, and commit a new db with the tables created by `create_tables`
+pub(crate) fn init_db(create_tables: impl FnOnce(&mut Db) -> Result<()>) {
+    with_backend(|memory| *memory = MemoryStorage::default());
+
+    let mut db = Db::new().unwrap();
+    create_tables(&mut db).unwrap();
+    db.commit().unwrap();
+}
+
+/// The record without index
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub(crate) struct Person {
+    pub(crate) age: u8,
+    pub(crate) trusted: bool,
+}
+impl Record for Person {}
+
+/// The record with the unique index of `serial` and the index of `age`
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub(crate) struct Member {
+    pub(crate) serial: u32,
+    pub(crate) age: u8,
+    pub(crate) active: bool,
+}
+impl Record for Member {
+    const INDEXES: &'static [(&'static str, bool)] = &[("serial", true), ("age", false)];
+
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        match field {
+            "serial" => Ok(to_ordered_bytes(&self.serial)?),
+            "age" => Ok(to_ordered_bytes(&self.age)?),
+            "active" => Ok(to_ordered_bytes(&self.active)?),
+            _ => Err(Error::UnknownField(field.into()).into()),
+        }
+    }
+}
+
+/// The record with the unique index of `serial`, which is saved in the heap if the title is
+/// longer than the row
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub(crate) struct Post {
+    pub(crate) serial: u32,
+    pub(crate) title: String,
+}
+impl Record for Post {
+    const INDEXES: &'static [(&'static str, bool)] = &[("serial", true)];
+
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        match field {
+            "serial" => Ok(to_ordered_bytes(&self.serial)?),
+            _ => Err(Error::UnknownField(field.into()).into()),
+        }
+    }
+}
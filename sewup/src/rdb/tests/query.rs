@@ -0,0 +1,190 @@
+use crate::ordered::to_ordered_bytes;
+use crate::rdb::errors::Error;
+use crate::rdb::query::{Aggregate, Condition, Order, Predicate};
+use crate::rdb::tests::{init_db, Member};
+use crate::rdb::traits::Record;
+use crate::rdb::Db;
+use crate::{Deserialize, Serialize, Value};
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+#[test]
+fn test_query_records_in_memory() {
+    init_db(|db| db.create_table::<Member>());
+
+    let mut members = Db::load(None).unwrap().table::<Member>().unwrap();
+    for age in [30u8, 10, 50, 20, 40].iter() {
+        members
+            .add_record(Member {
+                serial: *age as u32,
+                age: *age,
+                active: *age != 40,
+            })
+            .unwrap();
+    }
+    members.update_record(4, None).unwrap();
+
+    let ids = |records: Vec<(usize, Member)>| -> Vec<usize> {
+        records.into_iter().map(|(id, _)| id).collect()
+    };
+    let adults = vec![Predicate::new("age", Condition::ge(&18u8))];
+    assert_eq!(
+        ids(members.query(&adults, None, 0, None).unwrap()),
+        vec![1, 3, 5]
+    );
+    assert_eq!(members.count(&adults).unwrap(), 3);
+
+    let order_by = ("age".to_string(), Order::Desc);
+    assert_eq!(
+        ids(members.query(&adults, Some(&order_by), 1, Some(1)).unwrap()),
+        vec![5]
+    );
+
+    let predicates = vec![
+        Predicate::new("age", Condition::one_of(&[10u8, 40, 50])),
+        Predicate::new("active", Condition::equal(&false).not()),
+    ];
+    assert_eq!(
+        ids(members.query(&predicates, None, 0, None).unwrap()),
+        vec![2, 3]
+    );
+
+    let predicates = vec![Predicate::new("active", Condition::lt(&true))];
+    assert_eq!(
+        ids(members.query(&predicates, None, 0, None).unwrap()),
+        vec![5]
+    );
+
+    let predicates = vec![Predicate::new("missing", Condition::gt(&1u8))];
+    assert!(members.query(&predicates, None, 0, None).is_err());
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Purchase {
+    customer: u8,
+    amount: u64,
+    discount: Option<i32>,
+    note: String,
+}
+impl Record for Purchase {
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        match field {
+            "customer" => Ok(to_ordered_bytes(&self.customer)?),
+            "amount" => Ok(to_ordered_bytes(&self.amount)?),
+            _ => Err(Error::UnknownField(field.into()).into()),
+        }
+    }
+}
+
+#[test]
+fn test_aggregate_records_in_memory() {
+    init_db(|db| db.create_table::<Purchase>());
+
+    let mut purchases = Db::load(None).unwrap().table::<Purchase>().unwrap();
+    for (customer, amount, discount) in [(2u8, 30u64, Some(-5)), (1, 10, None), (2, 50, None)]
+        .iter()
+        .cloned()
+    {
+        purchases
+            .add_record(Purchase {
+                customer,
+                amount,
+                discount,
+                ..Default::default()
+            })
+            .unwrap();
+    }
+    purchases
+        .add_record(Purchase {
+            customer: 1,
+            amount: 70,
+            ..Default::default()
+        })
+        .unwrap();
+    purchases.update_record(4, None).unwrap();
+
+    assert_eq!(purchases.sum("amount", &[]).unwrap(), 90);
+    assert_eq!(purchases.min("amount", &[]).unwrap(), Some(10));
+    assert_eq!(purchases.max("discount", &[]).unwrap(), Some(-5));
+    assert_eq!(purchases.sum("discount", &[]).unwrap(), -5);
+    let large = vec![Predicate::new("amount", Condition::gt(&100u64))];
+    assert_eq!(purchases.sum("amount", &large).unwrap(), 0);
+    assert_eq!(purchases.max("amount", &large).unwrap(), None);
+    assert_eq!(
+        purchases.aggregate(&Aggregate::Count, &large).unwrap(),
+        Some(0)
+    );
+
+    assert_eq!(
+        purchases
+            .group_by("customer", &Aggregate::Sum("amount".into()), &[])
+            .unwrap(),
+        vec![(Value::U8(1), Some(10)), (Value::U8(2), Some(80))]
+    );
+    let small = vec![Predicate::new("amount", Condition::lt(&40u64))];
+    assert_eq!(
+        purchases
+            .group_by("customer", &Aggregate::Count, &small)
+            .unwrap(),
+        vec![(Value::U8(1), Some(1)), (Value::U8(2), Some(1))]
+    );
+
+    let err = purchases.sum("note", &[]).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Error>(),
+        Some(&Error::FieldNotInteger("note".into()))
+    );
+    let err = purchases.max("missing", &[]).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Error>(),
+        Some(&Error::UnknownField("missing".into()))
+    );
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Rating {
+    score: f64,
+    votes: HashMap<String, u8>,
+}
+impl Record for Rating {
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        match field {
+            "score" => Ok(to_ordered_bytes(&self.score)?),
+            "votes" => Ok(to_ordered_bytes(&self.votes)?),
+            _ => Err(Error::UnknownField(field.into()).into()),
+        }
+    }
+}
+
+#[test]
+fn test_query_equal_floats_and_maps_in_memory() {
+    init_db(|db| db.create_table::<Rating>());
+
+    let votes: HashMap<String, u8> = (0..16u8).map(|i| (i.to_string(), i)).collect();
+    let mut ratings = Db::load(None).unwrap().table::<Rating>().unwrap();
+    ratings
+        .add_record(Rating {
+            score: 0.0,
+            votes: votes.clone(),
+        })
+        .unwrap();
+    ratings
+        .add_record(Rating {
+            score: f64::NAN,
+            ..Default::default()
+        })
+        .unwrap();
+    ratings.commit().unwrap();
+
+    // The values equal to the filter match the records, even if they are encoded from different
+    // bits or in different iteration orders
+    let ratings = Db::load(None).unwrap().table::<Rating>().unwrap();
+    let predicates = vec![Predicate::new("score", Condition::equal(&-0.0f64))];
+    assert_eq!(ratings.count(&predicates).unwrap(), 1);
+    let reordered: HashMap<String, u8> = (0..16u8).rev().map(|i| (i.to_string(), i)).collect();
+    let predicates = vec![Predicate::new("votes", Condition::equal(&reordered))];
+    assert_eq!(ratings.query(&predicates, None, 0, None).unwrap()[0].0, 1);
+    let predicates = vec![Predicate::new("score", Condition::equal(&-f64::NAN))];
+    assert_eq!(ratings.query(&predicates, None, 0, None).unwrap()[0].0, 2);
+}
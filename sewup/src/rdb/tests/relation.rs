@@ -0,0 +1,154 @@
+use crate::ordered::to_ordered_bytes;
+use crate::rdb::errors::Error;
+use crate::rdb::tests::{init_db, Person};
+use crate::rdb::traits::{ForeignKey, OnDelete, Record};
+use crate::rdb::Db;
+use crate::{Deserialize, Serialize};
+
+use anyhow::Result;
+
+macro_rules! child {
+    ($name:ident, $parent:ident, $t:ty, $on_delete:expr, $id:expr, $set_none:expr) => {
+        #[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+        struct $name {
+            parent_id: $t,
+        }
+        impl Record for $name {
+            const INDEXES: &'static [(&'static str, bool)] = &[("parent_id", false)];
+
+            fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+                match field {
+                    "parent_id" => Ok(to_ordered_bytes(&self.parent_id)?),
+                    _ => Err(Error::UnknownField(field.into()).into()),
+                }
+            }
+
+            fn foreign_keys() -> Vec<ForeignKey<Self>> {
+                vec![ForeignKey {
+                    field: "parent_id",
+                    parent: $parent::table_name,
+                    on_delete: $on_delete,
+                    id: $id,
+                    set_none: $set_none,
+                }]
+            }
+        }
+    };
+}
+child!(
+    Child,
+    Person,
+    usize,
+    OnDelete::Restrict,
+    |r| Some(r.parent_id),
+    None
+);
+child!(
+    Toy,
+    Person,
+    usize,
+    OnDelete::Cascade,
+    |r| Some(r.parent_id),
+    None
+);
+child!(
+    Wheel,
+    Toy,
+    usize,
+    OnDelete::Cascade,
+    |r| Some(r.parent_id),
+    None
+);
+child!(
+    Pet,
+    Person,
+    Option<usize>,
+    OnDelete::SetNone,
+    |r| r.parent_id,
+    Some(|r| r.parent_id = None)
+);
+
+#[test]
+fn test_foreign_keys_in_memory() {
+    init_db(|db| {
+        db.create_table::<Person>()?;
+        db.create_table::<Child>()?;
+        db.create_table::<Toy>()?;
+        db.create_table::<Wheel>()?;
+        db.create_table::<Pet>()
+    });
+
+    let mut children = Db::load(None).unwrap().table::<Child>().unwrap();
+    children.add_record(Child { parent_id: 1 }).unwrap();
+    assert_eq!(
+        children.commit().unwrap_err().downcast::<Error>().unwrap(),
+        Error::ForeignKeyViolation("parent_id".into())
+    );
+
+    let mut parents = Db::load(None).unwrap().table::<Person>().unwrap();
+    parents
+        .add_record(Person {
+            age: 40,
+            trusted: true,
+        })
+        .unwrap();
+    parents
+        .add_record(Person {
+            age: 30,
+            trusted: true,
+        })
+        .unwrap();
+    parents.commit().unwrap();
+
+    let mut children = Db::load(None).unwrap().table::<Child>().unwrap();
+    children.add_record(Child { parent_id: 1 }).unwrap();
+    children.commit().unwrap();
+    let mut toys = Db::load(None).unwrap().table::<Toy>().unwrap();
+    toys.add_record(Toy { parent_id: 2 }).unwrap();
+    toys.add_record(Toy { parent_id: 1 }).unwrap();
+    toys.commit().unwrap();
+    let mut wheels = Db::load(None).unwrap().table::<Wheel>().unwrap();
+    wheels.add_record(Wheel { parent_id: 1 }).unwrap();
+    wheels.add_record(Wheel { parent_id: 2 }).unwrap();
+    wheels.commit().unwrap();
+    let mut pets = Db::load(None).unwrap().table::<Pet>().unwrap();
+    pets.add_record(Pet { parent_id: Some(2) }).unwrap();
+    pets.add_record(Pet { parent_id: None }).unwrap();
+    pets.commit().unwrap();
+
+    let mut parents = Db::load(None).unwrap().table::<Person>().unwrap();
+    parents.update_record(1, None).unwrap();
+    assert_eq!(
+        parents.commit().unwrap_err().downcast::<Error>().unwrap(),
+        Error::ForeignKeyRestricted("parent_id".into())
+    );
+
+    let mut parents = Db::load(None).unwrap().table::<Person>().unwrap();
+    parents.update_record(2, None).unwrap();
+    parents.commit().unwrap();
+
+    // The children are deleted or detached on the commit of parent table, even the child
+    // tables are never committed again
+    let toys = Db::load(None).unwrap().table::<Toy>().unwrap();
+    assert!(toys.get_record(1).is_err());
+    assert_eq!(toys.get_record(2).unwrap(), Toy { parent_id: 1 });
+    assert_eq!(toys.find_by_index("parent_id", &2usize).unwrap(), vec![]);
+    let wheels = Db::load(None).unwrap().table::<Wheel>().unwrap();
+    assert!(wheels.get_record(1).is_err());
+    assert_eq!(wheels.get_record(2).unwrap(), Wheel { parent_id: 2 });
+    assert_eq!(wheels.find_by_index("parent_id", &1usize).unwrap(), vec![]);
+    let pets = Db::load(None).unwrap().table::<Pet>().unwrap();
+    assert_eq!(pets.get_record(1).unwrap(), Pet { parent_id: None });
+    assert_eq!(
+        pets.find_by_index("parent_id", &None::<usize>).unwrap(),
+        vec![(1, Pet { parent_id: None }), (2, Pet { parent_id: None })]
+    );
+    pets.commit().unwrap();
+
+    let pets = Db::load(None).unwrap().table::<Pet>().unwrap();
+    assert_eq!(pets.get_record(1).unwrap(), Pet { parent_id: None });
+    assert_eq!(
+        pets.find_by_index("parent_id", &Some(2usize)).unwrap(),
+        vec![]
+    );
+}
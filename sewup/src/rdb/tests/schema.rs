@@ -0,0 +1,190 @@
+use crate::ordered::to_ordered_bytes;
+use crate::rdb::db::record_raw_size;
+use crate::rdb::errors::Error;
+use crate::rdb::tests::{init_db, Person};
+use crate::rdb::traits::Record;
+use crate::rdb::Db;
+use crate::{Deserialize, Serialize};
+
+use anyhow::Result;
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct PersonV1 {
+    serial: u32,
+    age: u8,
+}
+impl Record for PersonV1 {
+    const INDEXES: &'static [(&'static str, bool)] = &[("serial", true)];
+    const SCHEMA_VERSION: u32 = 1;
+
+    fn table_name() -> &'static str {
+        "person"
+    }
+
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        match field {
+            "serial" => Ok(to_ordered_bytes(&self.serial)?),
+            _ => Err(Error::UnknownField(field.into()).into()),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct PersonV2 {
+    serial: u32,
+    age: u16,
+    name: String,
+}
+impl Record for PersonV2 {
+    const INDEXES: &'static [(&'static str, bool)] = &[("serial", true), ("age", false)];
+    const SCHEMA_VERSION: u32 = 2;
+
+    fn table_name() -> &'static str {
+        "person"
+    }
+
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        match field {
+            "serial" => Ok(to_ordered_bytes(&self.serial)?),
+            "age" => Ok(to_ordered_bytes(&self.age)?),
+            _ => Err(Error::UnknownField(field.into()).into()),
+        }
+    }
+}
+
+#[test]
+fn test_migrate_table_in_memory() {
+    init_db(|db| {
+        db.create_table::<PersonV1>()?;
+        db.create_table::<Person>()
+    });
+
+    let mut people = Db::load(None).unwrap().table::<PersonV1>().unwrap();
+    for serial in 1..=3 {
+        people
+            .add_record(PersonV1 {
+                serial,
+                age: serial as u8 * 10,
+            })
+            .unwrap();
+    }
+    people.update_record(2, None).unwrap();
+    people.commit().unwrap();
+
+    assert_eq!(
+        Db::load(None)
+            .unwrap()
+            .table::<PersonV2>()
+            .err()
+            .unwrap()
+            .downcast::<Error>()
+            .unwrap(),
+        Error::SchemaVersionMismatch("person".into(), 1)
+    );
+    assert!(Db::load(None)
+        .unwrap()
+        .migrate_table::<PersonV1, Person>(|p| Person {
+            age: p.age,
+            trusted: true,
+        })
+        .is_err());
+
+    let convert = |p: PersonV1| PersonV2 {
+        serial: p.serial,
+        age: p.age as u16 * 100,
+        name: format!("person with the serial number {}", p.serial),
+    };
+    Db::load(None)
+        .unwrap()
+        .migrate_table::<PersonV1, PersonV2>(convert)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let db = Db::load(None).unwrap();
+    assert_eq!(
+        db.table_info::<PersonV2>().unwrap().record_raw_size,
+        record_raw_size::<PersonV2>().unwrap()
+    );
+    let people = db.table::<PersonV2>().unwrap();
+    assert_eq!(
+        people.get_record(1).unwrap(),
+        convert(PersonV1 { serial: 1, age: 10 })
+    );
+    assert!(people.get_record(2).is_err());
+    assert_eq!(
+        people.find_by_index("age", &3000u16).unwrap(),
+        vec![(3, convert(PersonV1 { serial: 3, age: 30 }))]
+    );
+    assert!(Db::load(None).unwrap().table::<PersonV1>().is_err());
+
+    let mut people = Db::load(None).unwrap().table::<PersonV2>().unwrap();
+    assert!(people
+        .add_record(PersonV2 {
+            serial: 3,
+            ..Default::default()
+        })
+        .is_err());
+    assert_eq!(people.add_record(PersonV2::default()).unwrap(), 4);
+    people.commit().unwrap();
+    let people = Db::load(None).unwrap().table::<PersonV2>().unwrap();
+    assert_eq!(people.all_records().unwrap().len(), 3);
+}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Legacy {
+    value: u32,
+}
+impl Record for Legacy {}
+
+#[derive(Default, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Renamed {
+    value: u32,
+}
+impl Record for Renamed {
+    fn table_name() -> &'static str {
+        "renamed"
+    }
+}
+
+#[test]
+fn test_rename_table_in_memory() {
+    init_db(|db| {
+        db.create_table::<Legacy>()?;
+        db.create_table::<Person>()
+    });
+    let mut legacy = Db::load(None).unwrap().table::<Legacy>().unwrap();
+    legacy.add_record(Legacy { value: 7 }).unwrap();
+    legacy.commit().unwrap();
+
+    let mut db = Db::load(None).unwrap();
+    assert!(db.rename_table::<Renamed>("missing").is_err());
+    db.rename_table::<Renamed>(std::any::type_name::<Legacy>())
+        .unwrap();
+    db.commit().unwrap();
+
+    let mut db = Db::load(None).unwrap();
+    assert!(db.table_info::<Legacy>().is_none());
+    assert_eq!(
+        db.create_table::<Legacy>()
+            .unwrap_err()
+            .downcast::<Error>()
+            .unwrap(),
+        Error::TableSignatureUsed(std::any::type_name::<Legacy>().into())
+    );
+    assert!(db.rename_table::<Person>("renamed").is_err());
+    let mut renamed = db.table::<Renamed>().unwrap();
+    assert_eq!(renamed.get_record(1).unwrap(), Renamed { value: 7 });
+    renamed.add_record(Renamed { value: 8 }).unwrap();
+    renamed.commit().unwrap();
+
+    let mut db = Db::load(None).unwrap();
+    db.rename_table::<Legacy>("renamed").unwrap();
+    db.commit().unwrap();
+
+    let db = Db::load(None).unwrap();
+    assert!(db.aliases.is_empty());
+    assert!(db.table_info::<Renamed>().is_none());
+    let legacy = db.table::<Legacy>().unwrap();
+    assert_eq!(legacy.all_records().unwrap().len(), 2);
+}
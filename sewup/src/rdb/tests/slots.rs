@@ -0,0 +1,81 @@
+use crate::rdb::tests::{init_db, Person, Post};
+use crate::rdb::{Db, Feature};
+
+#[test]
+fn test_vacuum_table_in_memory() {
+    init_db(|db| {
+        db.create_table::<Post>()?;
+        db.create_table::<Person>()
+    });
+
+    let post = |serial: u32| Post {
+        serial,
+        title: if serial % 2 == 0 {
+            "a title longer than the row of the table".repeat(2)
+        } else {
+            String::new()
+        },
+    };
+    let person = Person {
+        age: 9,
+        trusted: true,
+    };
+    let mut posts = Db::load(None).unwrap().table::<Post>().unwrap();
+    for serial in 1..=6 {
+        posts.add_record(post(serial)).unwrap();
+    }
+    posts.commit().unwrap();
+    let mut people = Db::load(None).unwrap().table::<Person>().unwrap();
+    people.add_record(person.clone()).unwrap();
+    people.commit().unwrap();
+
+    let mut posts = Db::load(None).unwrap().table::<Post>().unwrap();
+    posts.update_record(2, None).unwrap();
+    posts.update_record(3, None).unwrap();
+    posts.update_record(5, None).unwrap();
+    assert_eq!(posts.vacuum(), 3);
+    assert_eq!(posts.vacuum(), 0);
+    assert!(posts.update_record(3, None).is_err());
+    posts.commit().unwrap();
+
+    let db = Db::load(None).unwrap();
+    let info = db.table_info::<Post>().unwrap();
+    assert_eq!(info.range.end - info.range.start, info.record_raw_size * 3);
+    let posts = db.table::<Post>().unwrap();
+    assert_eq!(posts.get_record(1).unwrap(), post(1));
+    assert_eq!(posts.get_record(4).unwrap(), post(4));
+    assert_eq!(posts.get_record(6).unwrap(), post(6));
+    assert!(posts.get_record(2).is_err());
+    assert!(posts.get_record(5).is_err());
+    assert!(posts.get_record(7).is_err());
+    assert_eq!(
+        posts.find_by_index("serial", &6u32).unwrap(),
+        vec![(6, post(6))]
+    );
+    let people = Db::load(None).unwrap().table::<Person>().unwrap();
+    assert_eq!(people.get_record(1).unwrap(), person);
+
+    let mut posts = Db::load(None).unwrap().table::<Post>().unwrap();
+    assert_eq!(posts.add_record(post(7)).unwrap(), 7);
+    posts.update_record(1, None).unwrap();
+    assert_eq!(posts.add_record(post(8)).unwrap(), 8);
+    posts.commit().unwrap();
+    let info = Db::load(None).unwrap().table_info::<Post>().unwrap();
+    assert_eq!(info.range.end - info.range.start, info.record_raw_size * 5);
+
+    let mut db = Db::load(None).unwrap();
+    db.enable_feature(Feature::ReuseRows);
+    db.commit().unwrap();
+    let mut posts = Db::load(None).unwrap().table::<Post>().unwrap();
+    assert_eq!(posts.add_record(post(9)).unwrap(), 9);
+    posts.commit().unwrap();
+
+    let db = Db::load(None).unwrap();
+    let info = db.table_info::<Post>().unwrap();
+    assert_eq!(info.range.end - info.range.start, info.record_raw_size * 5);
+    let posts = db.table::<Post>().unwrap();
+    assert!(posts.get_record(1).is_err());
+    assert_eq!(posts.get_record(9).unwrap(), post(9));
+    assert_eq!(posts.get_record(8).unwrap(), post(8));
+    assert_eq!(posts.all_records().unwrap().len(), 5);
+}
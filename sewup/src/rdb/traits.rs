@@ -3,45 +3,112 @@ use std::borrow::Borrow;
 
 use anyhow::Result;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
 
+use crate::{Deserialize, Serialize, SerializeTrait};
+
+use crate::header;
 use crate::rdb::errors::Error;
 use crate::types::Row;
 
-//TODO make Header bigger for big object storage
-pub const HEADER_SIZE: u32 = 1;
+/// The number of bytes of the header of record, which is the versioned header of
+/// [`header`](crate::header)
+pub const HEADER_SIZE: u32 = header::HEADER_SIZE as u32;
+
+/// The header of the row pointing to the record saved in the overflow heap of table, which is
+/// never a header of the record saved in the row.
+pub const OVERFLOW_HEADER: u8 = 0xFF;
+
+/// The action on the child records when the parent record is deleted
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OnDelete {
+    /// The parent record can not be deleted if there are child records
+    Restrict,
+    /// The child records are deleted with the parent record
+    Cascade,
+    /// The foreign keys of child records are set to `None`, only for `#[belongs_none_or]`
+    SetNone,
+}
+
+/// The foreign key field of record, which is the id of the record in parent table
+pub struct ForeignKey<T> {
+    pub field: &'static str,
+    /// The name of parent table
+    pub parent: fn() -> &'static str,
+    pub on_delete: OnDelete,
+    /// The id of parent record, `None` if the record belongs to none
+    pub id: fn(&T) -> Option<usize>,
+    /// Set the foreign key to `None`, only for `#[belongs_none_or]`
+    pub set_none: Option<fn(&mut T)>,
+}
 
 /// helps to serialize struct to row or deserialized from row
 /// ```compile_fail
-/// | 1st bytes | ...    | padding                   |
-/// |-----------|--------|---------------------------|
-/// | Header    | Binary | padding to n times Byte32 |
+/// | 1st ~ 5th bytes | ...    | padding                   |
+/// |-----------------|--------|---------------------------|
+/// | Header          | Binary | padding to n times Byte32 |
 /// ```
-/// Header is the number of bytes for binary,
+/// Header is the version and the number of bytes for binary, the rows saved with the one-byte
+/// header of previous versions are still decoded.
 /// Record can be delete by simple mark the header zero
-pub trait Record: Sized + Serialize + DeserializeOwned {
+///
+/// If the record does not fit the size of row, for example the record has `String` or `Vec`
+/// fields, the binary is saved in the overflow heap of table, and the row is a pointer.
+/// ```compile_fail
+/// | 1st bytes       | 2nd ~ 5th bytes         | ...     |
+/// |-----------------|-------------------------|---------|
+/// | OVERFLOW_HEADER | size of binary (BE)     | padding |
+/// ```
+///
+/// The indexed fields are listed in `INDEXES` with the uniqueness, and `field_key` gives the
+/// order-preserving encoding of the field for the indexes and queries, these are generated by
+/// the `Table` derive with `#[index]` and `#[unique]`.
+///
+/// The foreign keys referencing the parent tables are listed by `foreign_keys`, which are
+/// generated by the `Table` derive with `#[belongs_to]` and `#[belongs_none_or]`.
+///
+/// The table is named by `table_name`, which is the type name of struct by default, and the
+/// records are saved with `SCHEMA_VERSION`, these can be set by the `Table` derive with
+/// `#[table(name = "...", version = ...)]`. The records saved with an earlier version are
+/// rewritten by `Db::migrate_table`, and the table saved with another name is associated by
+/// `Db::rename_table`.
+pub trait Record: Sized + SerializeTrait + DeserializeOwned {
+    const INDEXES: &'static [(&'static str, bool)] = &[];
+
+    const SCHEMA_VERSION: u32 = 0;
+
+    /// The name of table, the signature of table is the hash of the name
+    fn table_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn field_key(&self, field: &str) -> Result<Vec<u8>> {
+        Err(Error::UnknownField(field.into()).into())
+    }
+
+    fn foreign_keys() -> Vec<ForeignKey<Self>> {
+        Vec::new()
+    }
+
     fn from_row(r: &Row) -> Option<Self> {
         let buffer: &[u8] = r.borrow();
-        let header = buffer[0] as usize;
-        return if header == 0 {
+        return if buffer[0] == 0 {
             None
         } else {
-            let instance: Self =
-                bincode::deserialize(&buffer[1..buffer.len() - header + 1]).expect("load a record");
+            let bin = header::decode(buffer).expect("load a record");
+            let instance: Self = bincode::deserialize(bin).expect("load a record");
             Some(instance)
         };
     }
 
+    /// The row of record padded to `row_length`, or `RecordNotSized` if the record does not fit
     fn to_row(&self, row_length: u32) -> Result<Row> {
-        let mut bin = bincode::serialize(&self).expect("serialize a record fail");
-        let length = bin.len();
-        let header = ((length + 1) & 31) as u8; // padding bytes
-        let mut vec = vec![header];
-        vec.append(&mut bin);
-        let row: Row = vec.into();
-
-        return if row.len() as u32 == row_length {
-            Ok(row)
+        let bin = bincode::serialize(&self).expect("serialize a record fail");
+        let mut vec = header::encode(&bin);
+
+        return if vec.len() <= row_length as usize * 32 {
+            vec.resize(row_length as usize * 32, 0);
+            Ok(vec.into())
         } else {
             Err(Error::RecordNotSized.into())
         };
@@ -0,0 +1,51 @@
+use anyhow::Result;
+
+use crate::rdb::db::Db;
+use crate::rdb::table::Table;
+use crate::rdb::traits::Record;
+use crate::storage::stage;
+use crate::SerializeTrait;
+
+/// The transaction of `Db::transaction`, the tables loaded from the transaction see the tables
+/// committed before in the same transaction.
+pub struct Transaction {
+    _private: (),
+}
+
+impl Transaction {
+    /// Load the db with the changes staged in the transaction
+    pub fn db(&self) -> Result<Db> {
+        Db::load(None)
+    }
+
+    /// Load the table with the changes staged in the transaction
+    pub fn table<T: SerializeTrait + Default + Sized + Record>(&self) -> Result<Table<T>> {
+        Db::load(None)?.table::<T>()
+    }
+
+    /// Stage the changes of table, which are saved when the transaction is done
+    pub fn commit<T: Record>(&self, table: Table<T>) -> Result<u32> {
+        table.commit()
+    }
+}
+
+impl Db {
+    /// Run the closure in a transaction, the commits of tables and db in the closure are staged
+    /// and saved in one pass when the closure returns `Ok`, and the storage is untouched if the
+    /// closure returns an error.
+    /// The slots moved by the commits of several tables are only written with the final value.
+    /// ```compile_fail
+    /// Db::transaction(|tx| {
+    ///     let mut people = tx.table::<Person>()?;
+    ///     let person_id = people.add_record(person)?;
+    ///     tx.commit(people)?;
+    ///     let mut posts = tx.table::<Post>()?;
+    ///     posts.add_record(Post { person_id, ..post })?;
+    ///     tx.commit(posts)?;
+    ///     Ok(person_id)
+    /// })?;
+    /// ```
+    pub fn transaction<R, F: FnOnce(&mut Transaction) -> Result<R>>(f: F) -> Result<R> {
+        stage(|| f(&mut Transaction { _private: () }))
+    }
+}
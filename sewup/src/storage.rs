@@ -0,0 +1,245 @@
+//! The storage allocator maps the indexes of slots into the addresses of storage.
+//! Each feature allocates the slots in its own namespace, such that the kv store, rdb database
+//! and the token balances can be used in the same contract without overlapping.
+//!
+//! The slots are read and written through the `StorageBackend`, which is the storage of ewasm
+//! runtime in contract, and a `HashMap` in memory on the host, such that the kv store and rdb
+//! database can be tested with `cargo test` natively.
+//! ```ignore
+//! sewup::storage::with_backend(|memory| memory.block_number = 10);
+//! let store = sewup::kv::Store::load(None).unwrap();
+//! ```
+//!
+//! The slots stored in `stage` are kept in memory and read back by the following loads, they are
+//! written into the backend when the staged closure succeeds, and dropped if it fails.
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::utils::{sha3_256, storage_index_to_addr};
+
+/// The storage of contract, which is a map from 32 bytes address to 32 bytes value
+pub trait StorageBackend {
+    /// Load the value of slot, the slot never stored is zero
+    fn load(&self, addr: &[u8; 32]) -> [u8; 32];
+
+    /// Store the value into slot
+    fn store(&mut self, addr: &[u8; 32], value: &[u8; 32]);
+
+    /// The number of current block
+    fn block_number(&self) -> u64;
+}
+
+/// The storage of ewasm runtime
+#[cfg(target_arch = "wasm32")]
+pub struct EwasmStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl StorageBackend for EwasmStorage {
+    fn load(&self, addr: &[u8; 32]) -> [u8; 32] {
+        ewasm_api::storage_load(&(*addr).into()).bytes
+    }
+
+    fn store(&mut self, addr: &[u8; 32], value: &[u8; 32]) {
+        ewasm_api::storage_store(&(*addr).into(), &(*value).into());
+    }
+
+    fn block_number(&self) -> u64 {
+        ewasm_api::block_number()
+    }
+}
+
+/// The storage kept in memory, the block number can be set to simulate the commits in
+/// different blocks
+#[derive(Clone, Default)]
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+pub struct MemoryStorage {
+    pub slots: HashMap<[u8; 32], [u8; 32]>,
+    pub block_number: u64,
+}
+
+impl StorageBackend for MemoryStorage {
+    fn load(&self, addr: &[u8; 32]) -> [u8; 32] {
+        self.slots.get(addr).copied().unwrap_or([0u8; 32])
+    }
+
+    fn store(&mut self, addr: &[u8; 32], value: &[u8; 32]) {
+        if *value == [0u8; 32] {
+            self.slots.remove(addr);
+        } else {
+            self.slots.insert(*addr, *value);
+        }
+    }
+
+    fn block_number(&self) -> u64 {
+        self.block_number
+    }
+}
+
+/// The backend used by the kv store and rdb database
+#[cfg(target_arch = "wasm32")]
+pub type Backend = EwasmStorage;
+
+/// The backend used by the kv store and rdb database
+#[cfg(not(target_arch = "wasm32"))]
+pub type Backend = MemoryStorage;
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    static MEMORY_STORAGE: RefCell<MemoryStorage> = RefCell::new(MemoryStorage::default());
+}
+
+thread_local! {
+    /// The slots stored in `stage`, `None` if there is no staged closure running
+    static STAGED: RefCell<Option<HashMap<[u8; 32], [u8; 32]>>> = RefCell::new(None);
+}
+
+/// Access the backend, on the host the memory storage of current thread can be set or inspected
+#[cfg(target_arch = "wasm32")]
+pub fn with_backend<R, F: FnOnce(&mut Backend) -> R>(f: F) -> R {
+    f(&mut EwasmStorage)
+}
+
+/// Access the backend, on the host the memory storage of current thread can be set or inspected
+#[cfg(not(target_arch = "wasm32"))]
+pub fn with_backend<R, F: FnOnce(&mut Backend) -> R>(f: F) -> R {
+    MEMORY_STORAGE.with(|memory| f(&mut memory.borrow_mut()))
+}
+
+pub(crate) fn load(addr: &[u8; 32]) -> [u8; 32] {
+    STAGED
+        .with(|staged| staged.borrow().as_ref().and_then(|s| s.get(addr).copied()))
+        .unwrap_or_else(|| with_backend(|backend| backend.load(addr)))
+}
+
+pub(crate) fn store(addr: &[u8; 32], value: &[u8; 32]) {
+    let staged = STAGED.with(|staged| match staged.borrow_mut().as_mut() {
+        Some(s) => {
+            s.insert(*addr, *value);
+            true
+        }
+        None => false,
+    });
+    if !staged {
+        with_backend(|backend| backend.store(addr, value))
+    }
+}
+
+/// Run the closure with the stores staged in memory, the staged slots are written into the
+/// backend once if the closure succeeds, and none of them is written if the closure fails.
+/// The slots stored more than once are written with the last value, and the slots with the same
+/// value in the backend are not written.
+/// If `stage` is called in a staged closure, the slots are written when the outermost closure
+/// succeeds, and the slots stored in the inner closure are dropped if the inner closure fails.
+pub(crate) fn stage<R, F: FnOnce() -> Result<R>>(f: F) -> Result<R> {
+    let outer = STAGED.with(|staged| {
+        let mut staged = staged.borrow_mut();
+        let outer = staged.clone();
+        *staged = Some(outer.clone().unwrap_or_default());
+        outer
+    });
+    let output = f();
+    match (&output, outer) {
+        // The slots are kept for the outer closure
+        (Ok(_), Some(_)) => (),
+        (Ok(_), None) => {
+            let slots = STAGED
+                .with(|staged| staged.replace(None))
+                .unwrap_or_default();
+            with_backend(|backend| {
+                for (addr, value) in slots.iter() {
+                    if backend.load(addr) != *value {
+                        backend.store(addr, value);
+                    }
+                }
+            });
+        }
+        (Err(_), outer) => {
+            STAGED.with(|staged| staged.replace(outer));
+        }
+    }
+    output
+}
+
+pub(crate) fn block_number() -> u64 {
+    with_backend(|backend| backend.block_number())
+}
+
+/// The namespace of the header and slots of kv store
+pub const KV_NAMESPACE: &str = "kv";
+
+/// The namespace of the header and tables of rdb database
+pub const RDB_NAMESPACE: &str = "rdb";
+
+/// A space of contiguous slots, the 0th slot is the header of the space
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(any(feature = "debug", test), derive(Debug))]
+pub struct Namespace {
+    /// The address of the 0th slot, `None` for the root space used before namespaces
+    base: Option<[u8; 32]>,
+}
+
+impl Namespace {
+    /// The namespace starts from the hash of the name
+    pub fn new(name: &str) -> Self {
+        let mut input: Vec<u8> = "namespace".as_bytes().into();
+        input.extend_from_slice(name.as_bytes());
+        Self {
+            base: Some(sha3_256(&input)),
+        }
+    }
+
+    /// The root space starts from the zero address, which was shared by kv and rdb before the
+    /// namespaces, and it is only used to load the storage saved in previous versions.
+    pub fn root() -> Self {
+        Self { base: None }
+    }
+
+    /// The address of the slot with the index in the namespace
+    pub fn index_addr(&self, idx: usize) -> [u8; 32] {
+        let mut addr = [0u8; 32];
+        if let Some(base) = self.base {
+            addr = base;
+            let mut carry = idx as u128;
+            for byte in addr.iter_mut().rev() {
+                if carry == 0 {
+                    break;
+                }
+                let sum = *byte as u128 + (carry & 0xff);
+                *byte = sum as u8;
+                carry = (carry >> 8) + (sum >> 8);
+            }
+        } else {
+            storage_index_to_addr(idx, &mut addr);
+        }
+        addr
+    }
+}
+
+#[cfg(feature = "default")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_addr_of_namespace() {
+        let mut addr = [0u8; 32];
+        storage_index_to_addr(33, &mut addr);
+        assert_eq!(Namespace::root().index_addr(33), addr);
+        assert_eq!(Namespace::root().index_addr(0), [0u8; 32]);
+
+        let kv = Namespace::new(KV_NAMESPACE);
+        let rdb = Namespace::new(RDB_NAMESPACE);
+        assert_ne!(kv.index_addr(0), rdb.index_addr(0));
+        assert_ne!(kv.index_addr(0), [0u8; 32]);
+
+        let base = kv.index_addr(0);
+        let next = kv.index_addr(256);
+        assert_eq!(next[0..30], base[0..30]);
+        assert_eq!(
+            u16::from_be_bytes([next[30], next[31]]),
+            u16::from_be_bytes([base[30], base[31]]).wrapping_add(256)
+        );
+    }
+}
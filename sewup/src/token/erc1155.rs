@@ -6,6 +6,8 @@ use crate::primitives::Contract;
 use crate::types::Raw;
 use sewup_derive::ewasm_lib_fn;
 
+use super::erc165::interface_id;
+
 #[cfg(target_arch = "wasm32")]
 use super::helpers::{
     copy_into_address, copy_into_storage_value, get_approval, get_token_balance, set_token_balance,
@@ -30,6 +32,16 @@ pub use super::erc721::{
     SET_APPROVAL_FOR_ALL_ABI, SET_APPROVAL_FOR_ALL_SIG,
 };
 
+/// The interface id of ERC-1155 for ERC-165
+pub const INTERFACE_ID: [u8; 4] = interface_id(&[
+    SAFE_TRANSFER_FROM_SIG,
+    SAFE_BATCH_TRANSFER_FROM_SIG,
+    BALANCE_OF_SIG,
+    BALANCE_OF_BATCH_SIG,
+    SET_APPROVAL_FOR_ALL_SIG,
+    IS_APPROVED_FOR_ALL_SIG,
+]);
+
 /// Implement ERC-1155 balanceOf(address,uint256)
 #[ewasm_lib_fn(00fdd58e,
     constant=true,
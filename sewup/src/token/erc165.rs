@@ -0,0 +1,72 @@
+use crate::primitives::Contract;
+use sewup_derive::ewasm_lib_fn;
+
+#[cfg(target_arch = "wasm32")]
+use crate::utils::ewasm_return_bool;
+
+/// The interface id of ERC-165, which is the selector of supportsInterface(bytes4)
+pub const INTERFACE_ID: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+
+/// The interface id is the XOR of the selectors of the functions in the interface, such that the
+/// interface id of a contract can be derived from the `*_SIG` of the handlers it routes.
+pub const fn interface_id(sigs: &[[u8; 4]]) -> [u8; 4] {
+    let mut id = [0u8; 4];
+    let mut i = 0;
+    while i < sigs.len() {
+        id[0] ^= sigs[i][0];
+        id[1] ^= sigs[i][1];
+        id[2] ^= sigs[i][2];
+        id[3] ^= sigs[i][3];
+        i += 1;
+    }
+    id
+}
+
+/// Check the interface is supported, ERC-165 is always supported and `0xffffffff` never is
+pub fn supports(interface_id: &[u8; 4], interface_ids: &[[u8; 4]]) -> bool {
+    *interface_id != [0xff; 4]
+        && (*interface_id == INTERFACE_ID || interface_ids.contains(interface_id))
+}
+
+/// Implement ERC-165 supportsInterface(bytes4) with the interface ids of the contract, which
+/// reverts if the interface id is missing in the input
+/// ```compile_fail
+/// sewup::token::erc165::SUPPORTS_INTERFACE_SIG => sewup::token::erc165::supports_interface(
+///     &contract,
+///     &[erc721::INTERFACE_ID, erc721::METADATA_INTERFACE_ID],
+/// ),
+/// ```
+#[ewasm_lib_fn("01ffc9a7",
+    constant=true,
+    inputs=[{ "name": "interfaceId", "type": "bytes4" }],
+    outputs=[{ "name": "", "type": "bool" }]
+)]
+pub fn supports_interface(contract: &Contract, interface_ids: &[[u8; 4]]) {
+    match contract.input_data.get(4..8).map(<[u8; 4]>::try_from) {
+        Some(Ok(interface_id)) => ewasm_return_bool(supports(&interface_id, interface_ids)),
+        _ => ewasm_api::revert(),
+    }
+}
+
+#[cfg(feature = "default")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::{erc1155, erc20, erc721};
+
+    #[test]
+    fn test_interface_ids() {
+        assert_eq!(interface_id(&[SUPPORTS_INTERFACE_SIG]), INTERFACE_ID);
+        assert_eq!(erc20::INTERFACE_ID, [0x36, 0x37, 0x2b, 0x07]);
+        assert_eq!(erc721::INTERFACE_ID, [0x80, 0xac, 0x58, 0xcd]);
+        assert_eq!(erc721::METADATA_INTERFACE_ID, [0x5b, 0x5e, 0x13, 0x9f]);
+        assert_eq!(erc721::ENUMERABLE_INTERFACE_ID, [0x78, 0x0e, 0x9d, 0x63]);
+        assert_eq!(erc1155::INTERFACE_ID, [0xd9, 0xb6, 0x7a, 0x26]);
+
+        let interface_ids = [erc721::INTERFACE_ID];
+        assert!(supports(&INTERFACE_ID, &interface_ids));
+        assert!(supports(&erc721::INTERFACE_ID, &interface_ids));
+        assert!(!supports(&erc1155::INTERFACE_ID, &interface_ids));
+        assert!(!supports(&[0xff; 4], &[[0xff; 4]]));
+    }
+}
@@ -11,8 +11,8 @@ use crate::types::Raw;
 
 #[cfg(target_arch = "wasm32")]
 use super::helpers::{
-    copy_into_address, copy_into_storage_value, get_allowance, get_balance, set_allowance,
-    set_balance,
+    copy_into_address, copy_into_storage_value, get_allowance, get_balance, get_supply_cap,
+    get_total_supply, set_allowance, set_balance, set_supply_cap, set_total_supply,
 };
 
 #[cfg(target_arch = "wasm32")]
@@ -26,6 +26,18 @@ use hex::decode;
 
 use sewup_derive::ewasm_lib_fn;
 
+use super::erc165::interface_id;
+
+/// The interface id of ERC-20 for ERC-165
+pub const INTERFACE_ID: [u8; 4] = interface_id(&[
+    TOTAL_SUPPLY_SIG,
+    BALANCE_OF_SIG,
+    TRANSFER_SIG,
+    TRANSFER_FROM_SIG,
+    APPROVE_SIG,
+    ALLOWANCE_SIG,
+]);
+
 /// Implement ERC-20 transfer(address,uint256)
 #[ewasm_lib_fn(a9059cbb,
     inputs=[
@@ -82,7 +94,7 @@ pub fn transfer(contract: &Contract) {
             .try_into()
             .unwrap();
     log3(
-        &Vec::<u8>::with_capacity(0),
+        &value.to_be_bytes(),
         &topic.into(),
         &Raw::from(sender).to_bytes32().into(),
         &Raw::from(recipient).to_bytes32().into(),
@@ -130,15 +142,26 @@ pub fn decimals(i: u8) {
     ewasm_api::finish_data(&Raw::from(i).as_bytes().to_vec());
 }
 
-/// Implement ERC-20 totalSupply()
-/// ```json
-/// ```
+/// Implement ERC-20 totalSupply(), which is the supply minted and not burned
+///
+/// This is a breaking change from `total_supply(i: usize)`, which returned the supply passed in.
+/// The supply is now stored by `mint` and reduced by `burn` and `burnFrom`, so a contract mints
+/// its initial supply in the constructor and passes the contract here instead.
 #[ewasm_lib_fn(18160ddd,
     constant=true,
     outputs=[{ "internalType": "uint256", "name": "", "type": "uint256" }]
 )]
-pub fn total_supply(i: usize) {
-    ewasm_api::finish_data(&Raw::from(i).as_bytes().to_vec());
+pub fn total_supply(_contract: &Contract) {
+    ewasm_api::finish_data(&get_total_supply().bytes);
+}
+
+/// Implement ERC-20 cap(), which is zero if the supply is not capped
+#[ewasm_lib_fn("355274ea",
+    constant=true,
+    outputs=[{ "internalType": "uint256", "name": "", "type": "uint256" }]
+)]
+pub fn cap(_contract: &Contract) {
+    ewasm_api::finish_data(&get_supply_cap().bytes);
 }
 
 /// Implement ERC-20 approve(address,uint256)
@@ -164,7 +187,7 @@ pub fn approve(contract: &Contract) {
             .try_into()
             .unwrap();
     log3(
-        &Vec::<u8>::with_capacity(0),
+        &value.bytes,
         &topic.into(),
         &Raw::from(sender).to_bytes32().into(),
         &Raw::from(spender).to_bytes32().into(),
@@ -227,9 +250,14 @@ pub fn transfer_from(contract: &Contract) {
     }
 
     owner_balance = owner_balance - amount;
+    let origin_recipient_balance = recipient_balance;
     recipient_balance = recipient_balance + amount;
     allowed = allowed - amount;
 
+    if origin_recipient_balance > recipient_balance {
+        ewasm_api::revert();
+    }
+
     let owner_storage_value = {
         let buffer = owner_balance.to_be_bytes();
         copy_into_storage_value(&buffer)
@@ -255,17 +283,120 @@ pub fn transfer_from(contract: &Contract) {
             .try_into()
             .unwrap();
     log3(
-        &Vec::<u8>::with_capacity(0),
+        &amount.to_be_bytes(),
         &topic.into(),
         &Raw::from(owner).to_bytes32().into(),
         &Raw::from(recipient).to_bytes32().into(),
     );
 }
 
+/// Burn the amount from the balance of owner and the total supply, and log the transfer to the
+/// zero address
+#[cfg(target_arch = "wasm32")]
+fn do_burn(owner: Address, amount: Uint256) {
+    let owner_balance = Uint256::from_be_bytes(get_balance(&owner).bytes);
+    let supply = Uint256::from_be_bytes(get_total_supply().bytes);
+    if owner_balance < amount || supply < amount {
+        ewasm_api::revert();
+    }
+
+    set_balance(
+        &owner,
+        &copy_into_storage_value(&(owner_balance - amount).to_be_bytes()),
+    );
+    set_total_supply(&copy_into_storage_value(&(supply - amount).to_be_bytes()));
+
+    let topic: [u8; 32] =
+        decode("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")
+            .unwrap()
+            .try_into()
+            .unwrap();
+    log3(
+        &amount.to_be_bytes(),
+        &topic.into(),
+        &Raw::from(owner).to_bytes32().into(),
+        &Raw::from(0u32).to_bytes32().into(),
+    );
+}
+
+/// Implement ERC-20 burn(uint256), which burns the tokens of caller
+#[ewasm_lib_fn("42966c68",
+    inputs=[{ "internalType": "uint256", "name": "amount", "type": "uint256" }],
+    stateMutability=nonpayable
+)]
+pub fn burn(contract: &Contract) {
+    let amount = {
+        let buffer: [u8; 32] = contract.input_data[4..36].try_into().unwrap();
+        Uint256::from_be_bytes(buffer)
+    };
+    do_burn(caller(), amount);
+}
+
+/// Implement ERC-20 burnFrom(address,uint256), which burns the tokens of account with the
+/// allowance of caller
+#[ewasm_lib_fn("79cc6790",
+    inputs=[
+        { "internalType": "address", "name": "account", "type": "address" },
+        { "internalType": "uint256", "name": "amount", "type": "uint256" }
+    ],
+    name=burnFrom,
+    stateMutability=nonpayable
+)]
+pub fn burn_from(contract: &Contract) {
+    let sender = caller();
+    let owner = copy_into_address(&contract.input_data[16..36]);
+    let amount = {
+        let buffer: [u8; 32] = contract.input_data[36..68].try_into().unwrap();
+        Uint256::from_be_bytes(buffer)
+    };
+
+    let allowed = Uint256::from_be_bytes(get_allowance(&owner, &sender).bytes);
+    if amount > allowed {
+        ewasm_api::revert();
+    }
+    set_allowance(
+        &owner,
+        &sender,
+        &copy_into_storage_value(&(allowed - amount).to_be_bytes()),
+    );
+
+    do_burn(owner, amount);
+}
+
+/// Cap the total supply, the `mint` exceeding the cap reverts, and the supply is not capped if
+/// the cap is zero.
+#[cfg(target_arch = "wasm32")]
+pub fn set_cap(cap: usize) {
+    let cap = Uint256::from_u64(cap as u64).expect("cap should valid");
+    if cap < Uint256::from_be_bytes(get_total_supply().bytes) {
+        ewasm_api::revert();
+    }
+    set_supply_cap(&copy_into_storage_value(&cap.to_be_bytes()));
+}
+
+/// Mint the value to the address, which is added to the balance and the total supply
 #[cfg(target_arch = "wasm32")]
 pub fn mint(addr: &str, value: usize) {
     let address = Address::from_str(addr).expect("address invalid");
-    set_balance(&address, &Raw::from(value).to_bytes32().into());
+    let value = Uint256::from_u64(value as u64).expect("value should valid");
+
+    let balance = Uint256::from_be_bytes(get_balance(&address).bytes);
+    let supply = Uint256::from_be_bytes(get_total_supply().bytes);
+    let cap = Uint256::from_be_bytes(get_supply_cap().bytes);
+    let new_balance = balance + value;
+    let new_supply = supply + value;
+    if balance > new_balance
+        || supply > new_supply
+        || (cap != Uint256::default() && new_supply > cap)
+    {
+        ewasm_api::revert();
+    }
+
+    set_balance(
+        &address,
+        &copy_into_storage_value(&new_balance.to_be_bytes()),
+    );
+    set_total_supply(&copy_into_storage_value(&new_supply.to_be_bytes()));
 
     let topic: [u8; 32] =
         decode("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")
@@ -273,7 +404,7 @@ pub fn mint(addr: &str, value: usize) {
             .try_into()
             .unwrap();
     log3(
-        &Vec::<u8>::with_capacity(0),
+        &value.to_be_bytes(),
         &topic.into(),
         &Raw::from(0u32).to_bytes32().into(),
         &Raw::from(address).to_bytes32().into(),
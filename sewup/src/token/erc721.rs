@@ -10,19 +10,22 @@ use crate::types::Address;
 use crate::types::Raw;
 use sewup_derive::ewasm_lib_fn;
 
-pub use super::erc20::{
-    balance_of, name, symbol, BALANCE_OF_ABI, BALANCE_OF_SIG, NAME_ABI, NAME_SIG, SYMBOL_ABI,
-    SYMBOL_SIG,
-};
+use super::erc165::interface_id;
+
+pub use super::erc20::{balance_of, BALANCE_OF_ABI, BALANCE_OF_SIG};
 
 #[cfg(target_arch = "wasm32")]
 use super::helpers::{
-    copy_into_address, copy_into_storage_value, get_approval, get_balance, get_token_approval,
-    get_token_owner, set_approval, set_balance, set_token_approval, set_token_owner,
+    copy_into_address, copy_into_storage_value, decode_dynamic_bytes, get_approval, get_balance,
+    get_minter, get_name, get_owned_token, get_owned_token_index, get_symbol, get_token_approval,
+    get_token_by_index, get_token_owner, get_token_supply, get_token_uri, set_approval,
+    set_balance, set_minter as store_minter, set_name as store_name, set_owned_token,
+    set_owned_token_index, set_symbol as store_symbol, set_token_approval, set_token_by_index,
+    set_token_owner, set_token_supply, set_token_uri as store_token_uri,
 };
 
 #[cfg(target_arch = "wasm32")]
-use crate::utils::{caller, ewasm_return_bool};
+use crate::utils::{caller, ewasm_return_bool, ewasm_return_str};
 
 #[cfg(target_arch = "wasm32")]
 use bitcoin::util::uint::Uint256;
@@ -30,7 +33,37 @@ use bitcoin::util::uint::Uint256;
 use hex::decode;
 
 #[cfg(target_arch = "wasm32")]
-use ewasm_api::{log3, log4};
+use ewasm_api::types::EtherValue;
+#[cfg(target_arch = "wasm32")]
+use ewasm_api::{log3, log4, CallResult};
+
+/// The selector of `onERC721Received(address,address,uint256,bytes)`, which is also the magic
+/// value returned by the recipient accepting the token
+#[cfg(target_arch = "wasm32")]
+const ON_ERC721_RECEIVED_SIG: [u8; 4] = [0x15, 0x0b, 0x7a, 0x02];
+
+/// The interface id of ERC-721 for ERC-165
+pub const INTERFACE_ID: [u8; 4] = interface_id(&[
+    BALANCE_OF_SIG,
+    OWNER_OF_SIG,
+    SAFE_TRANSFER_FROM_WITH_DATA_SIG,
+    SAFE_TRANSFER_FROM_SIG,
+    TRANSFER_FROM_SIG,
+    APPROVE_SIG,
+    SET_APPROVAL_FOR_ALL_SIG,
+    GET_APPROVED_SIG,
+    IS_APPROVED_FOR_ALL_SIG,
+]);
+
+/// The interface id of the ERC-721 metadata extension for ERC-165
+pub const METADATA_INTERFACE_ID: [u8; 4] = interface_id(&[NAME_SIG, SYMBOL_SIG, TOKEN_URI_SIG]);
+
+/// The interface id of the ERC-721 enumeration extension for ERC-165
+pub const ENUMERABLE_INTERFACE_ID: [u8; 4] = interface_id(&[
+    TOTAL_SUPPLY_SIG,
+    TOKEN_BY_INDEX_SIG,
+    TOKEN_OF_OWNER_BY_INDEX_SIG,
+]);
 
 /// Implement ERC-721 owner_of()
 #[ewasm_lib_fn("6352211e",
@@ -46,14 +79,40 @@ pub fn owner_of(contract: &Contract) {
     ewasm_api::finish_data(&Raw::from(owner).as_bytes().to_vec());
 }
 
+/// Append the token to the tokens of owner at the index of the balance, which is not changed
+#[cfg(target_arch = "wasm32")]
+fn add_token_to_owner(owner: &Address, token_id: &[u8; 32]) {
+    let index = get_balance(owner).bytes;
+    set_owned_token(owner, &index, token_id);
+    set_owned_token_index(token_id, &index);
+}
+
+/// Remove the token from the tokens of owner by moving the last token of owner into its index,
+/// the balance is not changed
+#[cfg(target_arch = "wasm32")]
+fn remove_token_from_owner(owner: &Address, token_id: &[u8; 32]) {
+    let last_index = (Uint256::from_be_bytes(get_balance(owner).bytes)
+        - Uint256::from_u64(1u64).expect("uint256 one should valid"))
+    .to_be_bytes();
+    let index = get_owned_token_index(token_id);
+    if index != last_index {
+        let last_token = get_owned_token(owner, &last_index);
+        set_owned_token(owner, &index, &last_token);
+        set_owned_token_index(&last_token, &index);
+    }
+    set_owned_token(owner, &last_index, &[0u8; 32]);
+}
+
 #[cfg(target_arch = "wasm32")]
 fn do_transfer(owner: Address, to: Address, token_id: [u8; 32]) {
+    remove_token_from_owner(&owner, &token_id);
     let mut balance = get_balance(&owner);
     let mut value = Uint256::from_be_bytes(balance.bytes)
         - Uint256::from_u64(1u64).expect("uint256 one should valid");
     let mut buffer = value.to_be_bytes();
     set_balance(&owner, &copy_into_storage_value(&buffer));
 
+    add_token_to_owner(&to, &token_id);
     balance = get_balance(&to);
     value = Uint256::from_be_bytes(balance.bytes)
         + Uint256::from_u64(1u64).expect("uint256 one should valid");
@@ -102,6 +161,8 @@ pub fn transfer(contract: &Contract) {
 }
 
 /// Implement ERC-721 transferFrom(address,address,uint256)
+/// Throws unless `msg.sender` is the current owner, an authorized operator, or the approved
+/// address for this NFT. Throws if `_from` is not the current owner.
 #[ewasm_lib_fn("23b872dd",
   inputs=[
     { "name": "_from", "type": "address" },
@@ -116,11 +177,16 @@ pub fn transfer_from(contract: &Contract) {
     let to = copy_into_address(&contract.input_data[48..68]);
     let token_id = contract.input_data[68..100].try_into().unwrap();
 
-    if sender != get_token_approval(&token_id) && !get_approval(&owner, &sender) {
+    // The token is removed from the tokens of `_from`, which should be the owner
+    if owner == Address::default() || get_token_owner(&token_id) != owner {
+        ewasm_api::revert();
+    }
+    if sender != owner && sender != get_token_approval(&token_id) && !get_approval(&owner, &sender)
+    {
         ewasm_api::revert();
     }
 
-    do_transfer(owner.into(), to, token_id);
+    do_transfer(owner, to, token_id);
 }
 
 /// Implement ERC-721 approve(address,uint256)
@@ -209,15 +275,173 @@ pub fn is_approved_for_all(contract: &Contract) {
     ewasm_return_bool(get_approval(&owner, &operator));
 }
 
-/// Implement ERC-721 tokenMetadata(uint256)
+/// Implement ERC-721 totalSupply()
+#[ewasm_lib_fn("18160ddd",
+    constant=true,
+    outputs=[{ "name": "", "type": "uint256" }]
+)]
+pub fn total_supply(_contract: &Contract) {
+    ewasm_api::finish_data(&get_token_supply());
+}
+
+/// Implement ERC-721 tokenByIndex(uint256)
+/// Throws if `_index` >= `totalSupply()`.
+#[ewasm_lib_fn("4f6ccce7",
+    constant=true,
+    inputs=[{ "name": "_index", "type": "uint256" }],
+    outputs=[{ "name": "", "type": "uint256" }]
+)]
+pub fn token_by_index(contract: &Contract) {
+    let index: [u8; 32] = contract.input_data[4..36]
+        .try_into()
+        .expect("index should be byte32");
+    if Uint256::from_be_bytes(index) >= Uint256::from_be_bytes(get_token_supply()) {
+        ewasm_api::revert();
+    }
+    ewasm_api::finish_data(&get_token_by_index(&index));
+}
+
+/// Implement ERC-721 tokenOfOwnerByIndex(address,uint256)
+/// Throws if `_index` >= `balanceOf(_owner)` or if `_owner` is the zero address.
+#[ewasm_lib_fn("2f745c59",
+    constant=true,
+    inputs=[
+        { "name": "_owner", "type": "address" },
+        { "name": "_index", "type": "uint256" }
+    ],
+    outputs=[{ "name": "", "type": "uint256" }]
+)]
+pub fn token_of_owner_by_index(contract: &Contract) {
+    let owner = copy_into_address(&contract.input_data[16..36]);
+    let index: [u8; 32] = contract.input_data[36..68]
+        .try_into()
+        .expect("index should be byte32");
+    if owner == Address::default()
+        || Uint256::from_be_bytes(index) >= Uint256::from_be_bytes(get_balance(&owner).bytes)
+    {
+        ewasm_api::revert();
+    }
+    ewasm_api::finish_data(&get_owned_token(&owner, &index));
+}
+
+/// Implement ERC-721 tokenURI(uint256)
+/// Throws if `_tokenId` is not a valid NFT, the uri is empty if it is not set by `setTokenURI`.
+#[ewasm_lib_fn("c87b56dd",
+    constant=true,
+    inputs=[ { "name": "_tokenId", "type": "uint256" } ],
+    outputs=[ { "name": "", "type": "string" } ],
+    name=tokenURI
+)]
+pub fn token_uri(contract: &Contract) {
+    let token_id: [u8; 32] = contract.input_data[4..36]
+        .try_into()
+        .expect("token id should be byte32");
+    if get_token_owner(&token_id) == Address::default() {
+        ewasm_api::revert();
+    }
+    ewasm_return_str(&get_token_uri(&token_id));
+}
+
+/// Implement ERC-721 name(), which is the name stored by `set_name`
+#[ewasm_lib_fn("06fdde03",
+    constant=true,
+    outputs=[ { "name": "_name", "type": "string" } ]
+)]
+pub fn name(_contract: &Contract) {
+    ewasm_return_str(&get_name());
+}
+
+/// Implement ERC-721 symbol(), which is the symbol stored by `set_symbol`
+#[ewasm_lib_fn("95d89b41",
+    constant=true,
+    outputs=[ { "name": "_symbol", "type": "string" } ]
+)]
+pub fn symbol(_contract: &Contract) {
+    ewasm_return_str(&get_symbol());
+}
+
+/// Implement ERC-721 tokenMetadata(uint256), which is the draft of tokenURI(uint256)
 #[ewasm_lib_fn("6914db60",
     constant=true,
     inputs=[ { "name": "_tokenId", "type": "uint256" } ],
     outputs=[ { "name": "_infoUrl", "type": "string" } ]
 )]
-pub fn token_metadata(_contract: &Contract) {
-    // TODO
-    // https://github.com/second-state/SewUp/issues/161
+pub fn token_metadata(contract: &Contract) {
+    token_uri(contract);
+}
+
+/// Set the uri of token, which is returned by tokenURI(uint256)
+/// Throws unless `msg.sender` is the minter, which is set by `set_minter`.
+/// Throws if `_tokenId` is not a valid NFT.
+#[ewasm_lib_fn("162094c4",
+    inputs=[
+        { "name": "_tokenId", "type": "uint256" },
+        { "name": "_tokenURI", "type": "string" }
+    ],
+    name=setTokenURI,
+    stateMutability=nonpayable
+)]
+pub fn set_token_uri(contract: &Contract) {
+    let token_id: [u8; 32] = contract.input_data[4..36]
+        .try_into()
+        .expect("token id should be byte32");
+    if caller() != get_minter() || get_token_owner(&token_id) == Address::default() {
+        ewasm_api::revert();
+    }
+
+    match decode_dynamic_bytes(&contract.input_data, 36).map(std::str::from_utf8) {
+        Some(Ok(uri)) => store_token_uri(&token_id, uri),
+        _ => ewasm_api::revert(),
+    }
+}
+
+/// Transfer the token with the checks of safeTransferFrom, and call `onERC721Received` with
+/// `data` if the recipient is a contract
+#[cfg(target_arch = "wasm32")]
+fn do_safe_transfer(contract: &Contract, data: &[u8]) {
+    let sender = caller();
+    let from = copy_into_address(&contract.input_data[16..36]);
+    let to = copy_into_address(&contract.input_data[48..68]);
+    let token_id: [u8; 32] = contract.input_data[68..100]
+        .try_into()
+        .expect("token id should be byte32");
+    let owner = get_token_owner(&token_id);
+
+    if to == Address::default() || owner == Address::default() || owner != from {
+        ewasm_api::revert();
+    }
+    if sender != owner && sender != get_token_approval(&token_id) && !get_approval(&owner, &sender)
+    {
+        ewasm_api::revert();
+    }
+
+    do_transfer(owner, to.clone(), token_id);
+
+    if ewasm_api::external_code_size(&to.inner) > 0 {
+        let mut input = ON_ERC721_RECEIVED_SIG.to_vec();
+        input.extend_from_slice(&Raw::from(sender).to_bytes32());
+        input.extend_from_slice(&Raw::from(from).to_bytes32());
+        input.extend_from_slice(&token_id);
+        input.extend_from_slice(&Raw::from(128u32).to_bytes32());
+        input.extend_from_slice(&Raw::from(data.len()).to_bytes32());
+        input.extend_from_slice(data);
+        input.resize(input.len() + (32 - data.len() % 32) % 32, 0);
+
+        match ewasm_api::call_mutable(
+            ewasm_api::gas_left(),
+            &to.inner,
+            &EtherValue::default(),
+            &input,
+        ) {
+            CallResult::Successful => {
+                let output = ewasm_api::returndata_acquire();
+                if output.len() < 4 || output[0..4] != ON_ERC721_RECEIVED_SIG {
+                    ewasm_api::revert();
+                }
+            }
+            _ => ewasm_api::revert(),
+        }
+    }
 }
 
 /// Implement ERC-721 safeTransferFrom(address,address,uint256,bytes)
@@ -228,13 +452,25 @@ pub fn token_metadata(_contract: &Contract) {
 /// checks if `_to` is a smart contract (code size > 0). If so, it calls
 /// `onERC721Received` on `_to` and throws if the return value is not
 /// `bytes4(keccak256("onERC721Received(address,address,uint256,bytes)"))`.
-#[ewasm_lib_fn("b88d4fde")]
-pub fn safe_transfer_from_with_data(_contract: &Contract) {
-    // TODO
-    // https://github.com/second-state/SewUp/issues/160
+#[ewasm_lib_fn("b88d4fde",
+  inputs=[
+    { "name": "_from", "type": "address" },
+    { "name": "_to", "type": "address" },
+    { "name": "_tokenId", "type": "uint256" },
+    { "name": "_data", "type": "bytes" }
+  ],
+  name=safeTransferFrom,
+  stateMutability=nonpayable
+)]
+pub fn safe_transfer_from_with_data(contract: &Contract) {
+    match decode_dynamic_bytes(&contract.input_data, 100) {
+        Some(data) => do_safe_transfer(contract, data),
+        None => ewasm_api::revert(),
+    }
 }
 
-/// Implement ERC-721 safeTransferFrom(address,address,uint256)
+/// Implement ERC-721 safeTransferFrom(address,address,uint256), which is
+/// safeTransferFrom(address,address,uint256,bytes) with empty data
 #[ewasm_lib_fn("42842e0e",
   inputs=[
     { "name": "_from", "type": "address" },
@@ -243,14 +479,34 @@ pub fn safe_transfer_from_with_data(_contract: &Contract) {
   ],
   stateMutability=nonpayable
 )]
-pub fn safe_transfer_from(_contract: &Contract) {
-    // TODO
-    // https://github.com/second-state/SewUp/issues/160
+pub fn safe_transfer_from(contract: &Contract) {
+    do_safe_transfer(contract, &[]);
 }
 
+/// Set the name of tokens, which is returned by name(), and is usually set in the constructor
+#[cfg(target_arch = "wasm32")]
+pub fn set_name(name: &str) {
+    store_name(name);
+}
+
+/// Set the symbol of tokens, which is returned by symbol(), and is usually set in the constructor
+#[cfg(target_arch = "wasm32")]
+pub fn set_symbol(symbol: &str) {
+    store_symbol(symbol);
+}
+
+/// Set the minter, who can set the uris of tokens, and is usually set in the constructor
+#[cfg(target_arch = "wasm32")]
+pub fn set_minter(addr: &str) {
+    let address = Address::from_str(addr).expect("address invalid");
+    store_minter(&address);
+}
+
+/// Mint the tokens to the address, which reverts if any of the tokens is already minted
 #[cfg(target_arch = "wasm32")]
 pub fn mint(addr: &str, tokens: Vec<&str>) {
     let address = Address::from_str(addr).expect("address invalid");
+    let one = Uint256::from_u64(1u64).expect("uint256 one should valid");
 
     let topic: [u8; 32] =
         decode("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")
@@ -262,7 +518,19 @@ pub fn mint(addr: &str, tokens: Vec<&str>) {
             .expect("token id should be hex format")
             .try_into()
             .expect("token id should be byte32");
+        if get_token_owner(&token_id) != Address::default() {
+            ewasm_api::revert();
+        }
         set_token_owner(&token_id, &address);
+
+        let supply = get_token_supply();
+        set_token_by_index(&supply, &token_id);
+        set_token_supply(&(Uint256::from_be_bytes(supply) + one).to_be_bytes());
+
+        add_token_to_owner(&address, &token_id);
+        let balance = Uint256::from_be_bytes(get_balance(&address).bytes) + one;
+        set_balance(&address, &copy_into_storage_value(&balance.to_be_bytes()));
+
         log4(
             &Vec::<u8>::with_capacity(0),
             &topic.into(),
@@ -271,6 +539,4 @@ pub fn mint(addr: &str, tokens: Vec<&str>) {
             &token_id.into(),
         );
     }
-
-    set_balance(&address, &Raw::from(tokens.len()).to_bytes32().into());
 }
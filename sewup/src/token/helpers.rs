@@ -12,6 +12,25 @@ use ewasm_api::types::{StorageKey, StorageValue};
 #[cfg(not(target_arch = "wasm32"))]
 pub struct StorageValue {}
 
+/// The content of the dynamic `bytes` or `string` argument, whose offset is at `head` of the
+/// input, `None` if the offset or the length is out of the input
+pub fn decode_dynamic_bytes(input: &[u8], head: usize) -> Option<&[u8]> {
+    let word = |pos: usize| -> Option<usize> {
+        let buffer = input.get(pos..pos.checked_add(32)?)?;
+        if buffer[0..24].iter().any(|b| *b != 0) {
+            return None;
+        }
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&buffer[24..32]);
+        usize::try_from(u64::from_be_bytes(bytes)).ok()
+    };
+    // The offset is counted from the arguments after the function selector
+    let start = word(head)?.checked_add(4)?;
+    let length = word(start)?;
+    let data_start = start.checked_add(32)?;
+    input.get(data_start..data_start.checked_add(length)?)
+}
+
 pub fn calculate_approval_hash(sender: &[u8; 20], spender: &[u8; 20]) -> Vec<u8> {
     let mut allowance: Vec<u8> = "approval".as_bytes().into();
     allowance.extend_from_slice(sender);
@@ -44,6 +63,60 @@ pub fn calculate_token_hash(token_id: &[u8; 32]) -> Vec<u8> {
     sha3_256(&token).to_vec()
 }
 
+pub fn calculate_token_uri_hash(token_id: &[u8; 32], idx: u32) -> Vec<u8> {
+    let mut token_uri: Vec<u8> = "token uri".as_bytes().into();
+    token_uri.extend_from_slice(token_id);
+    token_uri.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&token_uri).to_vec()
+}
+
+pub fn calculate_total_supply_hash() -> Vec<u8> {
+    sha3_256("total supply".as_bytes()).to_vec()
+}
+
+pub fn calculate_supply_cap_hash() -> Vec<u8> {
+    sha3_256("supply cap".as_bytes()).to_vec()
+}
+
+pub fn calculate_token_supply_hash() -> Vec<u8> {
+    sha3_256("token supply".as_bytes()).to_vec()
+}
+
+pub fn calculate_token_by_index_hash(index: &[u8; 32]) -> Vec<u8> {
+    let mut token: Vec<u8> = "token by index".as_bytes().into();
+    token.extend_from_slice(index);
+    sha3_256(&token).to_vec()
+}
+
+pub fn calculate_owned_token_hash(owner: &[u8; 20], index: &[u8; 32]) -> Vec<u8> {
+    let mut token: Vec<u8> = "owned token".as_bytes().into();
+    token.extend_from_slice(owner);
+    token.extend_from_slice(index);
+    sha3_256(&token).to_vec()
+}
+
+pub fn calculate_owned_token_index_hash(token_id: &[u8; 32]) -> Vec<u8> {
+    let mut index: Vec<u8> = "owned token index".as_bytes().into();
+    index.extend_from_slice(token_id);
+    sha3_256(&index).to_vec()
+}
+
+pub fn calculate_name_hash(idx: u32) -> Vec<u8> {
+    let mut name: Vec<u8> = "name".as_bytes().into();
+    name.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&name).to_vec()
+}
+
+pub fn calculate_symbol_hash(idx: u32) -> Vec<u8> {
+    let mut symbol: Vec<u8> = "symbol".as_bytes().into();
+    symbol.extend_from_slice(&idx.to_be_bytes());
+    sha3_256(&symbol).to_vec()
+}
+
+pub fn calculate_minter_hash() -> Vec<u8> {
+    sha3_256("minter".as_bytes()).to_vec()
+}
+
 pub fn calculate_token_balance_hash(address: &[u8; 20], token_id: &[u8; 32]) -> Vec<u8> {
     let mut balance_of: Vec<u8> = "balanceOf".as_bytes().into();
     balance_of.extend_from_slice(address);
@@ -219,3 +292,256 @@ pub fn get_token_owner(token_id: &[u8; 32]) -> Address {
         .expect("address should be bytes20");
     bytes20.into()
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_minter(_minter: &Address) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_minter(minter: &Address) {
+    let storage_key = copy_into_storage_value(&calculate_minter_hash());
+    let value: StorageValue = Raw::from(minter).to_bytes32().into();
+    ewasm_api::storage_store(&storage_key, &value);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_minter() -> Address {
+    Address::default()
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_minter() -> Address {
+    let storage_key = copy_into_storage_value(&calculate_minter_hash());
+    let storage_value = ewasm_api::storage_load(&storage_key);
+    let bytes20: [u8; 20] = storage_value.bytes[12..32]
+        .try_into()
+        .expect("address should be bytes20");
+    bytes20.into()
+}
+
+/// Load the string kept by `store_string`, `slot_hash` gives the hash of the slots by index
+#[cfg(target_arch = "wasm32")]
+fn load_string(slot_hash: impl Fn(u32) -> Vec<u8>) -> String {
+    let header = ewasm_api::storage_load(&copy_into_storage_value(&slot_hash(0)));
+    let length = u32::from_be_bytes(header.bytes[28..32].try_into().unwrap());
+    let mut bin = Vec::<u8>::with_capacity(length as usize + 31);
+    for i in 1..=(length + 31) / 32 {
+        let storage_key = copy_into_storage_value(&slot_hash(i));
+        bin.extend_from_slice(&ewasm_api::storage_load(&storage_key).bytes);
+    }
+    bin.truncate(length as usize);
+    String::from_utf8(bin).expect("string should be utf8")
+}
+
+/// The length of string is kept in the first slot, and the string is kept in the following
+/// slots, the slots of the previous string no longer used are cleared
+#[cfg(target_arch = "wasm32")]
+fn store_string(slot_hash: impl Fn(u32) -> Vec<u8>, s: &str) {
+    let header_key = copy_into_storage_value(&slot_hash(0));
+    let previous_length = u32::from_be_bytes(
+        ewasm_api::storage_load(&header_key).bytes[28..32]
+            .try_into()
+            .unwrap(),
+    );
+    ewasm_api::storage_store(&header_key, &Raw::from(s.len()).to_bytes32().into());
+
+    let length = s.len() as u32;
+    for (i, chunk) in s.as_bytes().chunks(32).enumerate() {
+        let storage_key = copy_into_storage_value(&slot_hash(i as u32 + 1));
+        ewasm_api::storage_store(
+            &storage_key,
+            &copy_into_storage_value(&Raw::from(chunk).to_bytes32()),
+        );
+    }
+    for i in (length + 31) / 32..(previous_length + 31) / 32 {
+        let storage_key = copy_into_storage_value(&slot_hash(i + 1));
+        ewasm_api::storage_store(&storage_key, &StorageValue::default());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_token_uri(_token_id: &[u8; 32]) -> String {
+    String::new()
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_token_uri(token_id: &[u8; 32]) -> String {
+    load_string(|i| calculate_token_uri_hash(token_id, i))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_token_uri(_token_id: &[u8; 32], _uri: &str) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_token_uri(token_id: &[u8; 32], uri: &str) {
+    store_string(|i| calculate_token_uri_hash(token_id, i), uri);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_name() -> String {
+    String::new()
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_name() -> String {
+    load_string(calculate_name_hash)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_name(_name: &str) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_name(name: &str) {
+    store_string(calculate_name_hash, name);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_symbol() -> String {
+    String::new()
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_symbol() -> String {
+    load_string(calculate_symbol_hash)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_symbol(_symbol: &str) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_symbol(symbol: &str) {
+    store_string(calculate_symbol_hash, symbol);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_token_supply() -> [u8; 32] {
+    [0u8; 32]
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_token_supply() -> [u8; 32] {
+    let storage_key = copy_into_storage_value(&calculate_token_supply_hash());
+    ewasm_api::storage_load(&storage_key).bytes
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_token_supply(_supply: &[u8; 32]) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_token_supply(supply: &[u8; 32]) {
+    let storage_key = copy_into_storage_value(&calculate_token_supply_hash());
+    ewasm_api::storage_store(&storage_key, &copy_into_storage_value(supply));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_token_by_index(_index: &[u8; 32]) -> [u8; 32] {
+    [0u8; 32]
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_token_by_index(index: &[u8; 32]) -> [u8; 32] {
+    let storage_key = copy_into_storage_value(&calculate_token_by_index_hash(index));
+    ewasm_api::storage_load(&storage_key).bytes
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_token_by_index(_index: &[u8; 32], _token_id: &[u8; 32]) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_token_by_index(index: &[u8; 32], token_id: &[u8; 32]) {
+    let storage_key = copy_into_storage_value(&calculate_token_by_index_hash(index));
+    ewasm_api::storage_store(&storage_key, &copy_into_storage_value(token_id));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_owned_token(_owner: &Address, _index: &[u8; 32]) -> [u8; 32] {
+    [0u8; 32]
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_owned_token(owner: &Address, index: &[u8; 32]) -> [u8; 32] {
+    let storage_key =
+        copy_into_storage_value(&calculate_owned_token_hash(&owner.inner.bytes, index));
+    ewasm_api::storage_load(&storage_key).bytes
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_owned_token(_owner: &Address, _index: &[u8; 32], _token_id: &[u8; 32]) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_owned_token(owner: &Address, index: &[u8; 32], token_id: &[u8; 32]) {
+    let storage_key =
+        copy_into_storage_value(&calculate_owned_token_hash(&owner.inner.bytes, index));
+    ewasm_api::storage_store(&storage_key, &copy_into_storage_value(token_id));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_owned_token_index(_token_id: &[u8; 32]) -> [u8; 32] {
+    [0u8; 32]
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_owned_token_index(token_id: &[u8; 32]) -> [u8; 32] {
+    let storage_key = copy_into_storage_value(&calculate_owned_token_index_hash(token_id));
+    ewasm_api::storage_load(&storage_key).bytes
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_owned_token_index(_token_id: &[u8; 32], _index: &[u8; 32]) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_owned_token_index(token_id: &[u8; 32], index: &[u8; 32]) {
+    let storage_key = copy_into_storage_value(&calculate_owned_token_index_hash(token_id));
+    ewasm_api::storage_store(&storage_key, &copy_into_storage_value(index));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_total_supply() -> StorageValue {
+    StorageValue {}
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_total_supply() -> StorageValue {
+    let storage_key = copy_into_storage_value(&calculate_total_supply_hash());
+    ewasm_api::storage_load(&storage_key)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_total_supply(_value: &StorageValue) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_total_supply(value: &StorageValue) {
+    let storage_key = copy_into_storage_value(&calculate_total_supply_hash());
+    ewasm_api::storage_store(&storage_key, value);
+}
+
+/// The supply cap is zero if the supply is not capped
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_supply_cap() -> StorageValue {
+    StorageValue {}
+}
+#[cfg(target_arch = "wasm32")]
+pub fn get_supply_cap() -> StorageValue {
+    let storage_key = copy_into_storage_value(&calculate_supply_cap_hash());
+    ewasm_api::storage_load(&storage_key)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_supply_cap(_value: &StorageValue) {}
+#[cfg(target_arch = "wasm32")]
+pub fn set_supply_cap(value: &StorageValue) {
+    let storage_key = copy_into_storage_value(&calculate_supply_cap_hash());
+    ewasm_api::storage_store(&storage_key, value);
+}
+
+#[cfg(feature = "default")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_dynamic_bytes() {
+        let mut input = vec![0u8; 4];
+        let mut word = [0u8; 32];
+        word[31] = 32;
+        input.extend_from_slice(&word);
+        word[31] = 3;
+        input.extend_from_slice(&word);
+        input.extend_from_slice(&[1, 2, 3]);
+        input.resize(100, 0);
+        assert_eq!(decode_dynamic_bytes(&input, 4), Some(&[1u8, 2, 3][..]));
+
+        // The length exceeds the input
+        input[67] = 200;
+        assert_eq!(decode_dynamic_bytes(&input, 4), None);
+        // The offset exceeds the input
+        input[35] = 250;
+        assert_eq!(decode_dynamic_bytes(&input, 4), None);
+        // The offset does not fit usize
+        input[35] = 32;
+        input[4] = 1;
+        assert_eq!(decode_dynamic_bytes(&input, 4), None);
+        assert_eq!(decode_dynamic_bytes(&input[0..20], 4), None);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod erc1155;
+pub mod erc165;
 pub mod erc20;
 pub mod erc721;
 pub mod helpers;
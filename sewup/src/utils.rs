@@ -49,11 +49,13 @@ pub fn ewasm_return(bytes: Vec<u8>) {
     finish_data(&bytes);
 }
 
+/// Return the string in ABI encoding, the string is padded to words of 32 bytes
 #[cfg(target_arch = "wasm32")]
 pub fn ewasm_return_str(s: &str) {
     let mut output = Raw::from(32u32).as_bytes().to_vec();
     output.append(&mut Raw::from(s.len()).as_bytes().to_vec());
-    output.append(&mut Raw::from(s).as_bytes().to_vec());
+    output.extend_from_slice(s.as_bytes());
+    output.resize(64 + (s.len() + 31) / 32 * 32, 0);
     finish_data(&output);
 }
 
@@ -1,5 +1,5 @@
 use serde_derive::{Deserialize, Serialize};
-use sewup_derive::Table;
+use sewup_derive::{foreign_keys, Table};
 
 #[derive(Table, Serialize, Deserialize)]
 struct SimpleStruct {
@@ -13,4 +13,47 @@ pub struct AnotherSimpleStruct {
     description: String,
 }
 
+#[derive(Table, Serialize, Deserialize)]
+pub struct IndexedStruct {
+    #[unique]
+    serial: u32,
+    #[index]
+    trust: bool,
+    description: String,
+}
+
+#[derive(Table, Serialize, Deserialize)]
+#[table(name = "versioned", version = 2)]
+pub struct VersionedStruct {
+    trust: bool,
+}
+
+#[foreign_keys]
+#[derive(Table, Default, Serialize, Deserialize)]
+#[belongs_none_or(TagStruct, on_delete = set_none)]
+#[has_many(RelatedStruct)]
+pub struct ParentStruct {
+    trust: bool,
+}
+
+#[foreign_keys]
+#[derive(Table, Default, Serialize, Deserialize)]
+#[belongs_to(ParentStruct, on_delete = cascade)]
+#[many_to_many(TagStruct, through = JoinStruct)]
+pub struct RelatedStruct {
+    description: String,
+}
+
+#[foreign_keys]
+#[derive(Table, Default, Serialize, Deserialize)]
+#[belongs_to(RelatedStruct)]
+#[belongs_to(TagStruct)]
+pub struct JoinStruct {}
+
+#[derive(Table, Default, Serialize, Deserialize)]
+#[has_many(ParentStruct)]
+pub struct TagStruct {
+    description: String,
+}
+
 fn main() {}